@@ -0,0 +1,12 @@
+-- Soft deletes so that removals propagate to other devices through /sync.
+-- A row with deleted_at set is a tombstone: hidden from the REST API but
+-- still returned to sync clients until the purge job removes it.
+
+ALTER TABLE archives ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE tomes ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+ALTER TABLE entries ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+
+-- Partial indexes for the purge job, which only ever looks at tombstones
+CREATE INDEX IF NOT EXISTS idx_archives_deleted_at ON archives(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_tomes_deleted_at ON tomes(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_entries_deleted_at ON entries(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,43 @@
+-- Server-assigned change sequence for /sync.
+-- Each user has a revision counter; every insert or update of one of their
+-- archives, tomes or entries bumps it and stamps the row with the new value.
+-- Bumping the counter takes a row lock on the user, so concurrent writers
+-- for the same user commit in revision order and a reader never observes a
+-- revision without also observing every earlier one.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS sync_revision BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE archives ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE tomes ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE entries ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 0;
+
+CREATE OR REPLACE FUNCTION bump_sync_revision() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE users
+    SET sync_revision = sync_revision + 1
+    WHERE id = NEW.user_id
+    RETURNING sync_revision INTO NEW.revision;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER archives_sync_revision
+    BEFORE INSERT OR UPDATE ON archives
+    FOR EACH ROW EXECUTE FUNCTION bump_sync_revision();
+
+CREATE TRIGGER tomes_sync_revision
+    BEFORE INSERT OR UPDATE ON tomes
+    FOR EACH ROW EXECUTE FUNCTION bump_sync_revision();
+
+CREATE TRIGGER entries_sync_revision
+    BEFORE INSERT OR UPDATE ON entries
+    FOR EACH ROW EXECUTE FUNCTION bump_sync_revision();
+
+-- Give existing rows a revision by touching them through the triggers
+UPDATE archives SET revision = revision;
+UPDATE tomes SET revision = revision;
+UPDATE entries SET revision = revision;
+
+CREATE INDEX IF NOT EXISTS idx_archives_user_revision ON archives(user_id, revision);
+CREATE INDEX IF NOT EXISTS idx_tomes_user_revision ON tomes(user_id, revision);
+CREATE INDEX IF NOT EXISTS idx_entries_user_revision ON entries(user_id, revision);
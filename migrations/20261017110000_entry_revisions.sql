@@ -0,0 +1,33 @@
+-- Snapshots of entry text keyed by the sync revision that produced them.
+-- Sync clients send the revision they edited from, and the server uses the
+-- matching snapshot as the base of a three-way merge against its current
+-- copy. Rows are only written when the title or content actually changes,
+-- so tombstoning or touching an entry does not grow the history.
+
+CREATE TABLE IF NOT EXISTS entry_revisions (
+    entry_id VARCHAR(255) NOT NULL,
+    revision BIGINT NOT NULL,
+    user_id UUID NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (entry_id, revision),
+    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE OR REPLACE FUNCTION record_entry_revision() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT'
+       OR NEW.title IS DISTINCT FROM OLD.title
+       OR NEW.content IS DISTINCT FROM OLD.content THEN
+        INSERT INTO entry_revisions (entry_id, revision, user_id, title, content)
+        VALUES (NEW.id, NEW.revision, NEW.user_id, NEW.title, NEW.content);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER entries_record_revision
+    AFTER INSERT OR UPDATE ON entries
+    FOR EACH ROW EXECUTE FUNCTION record_entry_revision();
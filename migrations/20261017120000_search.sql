@@ -0,0 +1,41 @@
+-- Full-text search over archives, tomes and entries.
+-- Names and titles weigh more than descriptions and content.
+
+ALTER TABLE archives ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(description, '')), 'B')
+    ) STORED;
+
+ALTER TABLE tomes ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(description, '')), 'B')
+    ) STORED;
+
+-- Entries get the same kind of generated column. The initial schema names
+-- the entry title `name`, while some databases were patched by hand to
+-- `title`, so the column is built on whichever of the two exists.
+DO $$
+DECLARE
+    title_column TEXT;
+BEGIN
+    SELECT column_name INTO title_column
+    FROM information_schema.columns
+    WHERE table_schema = current_schema() AND table_name = 'entries' AND column_name IN ('title', 'name')
+    ORDER BY column_name = 'title' DESC
+    LIMIT 1;
+
+    EXECUTE format(
+        'ALTER TABLE entries ADD COLUMN IF NOT EXISTS search_vector tsvector
+            GENERATED ALWAYS AS (
+                setweight(to_tsvector(''english'', coalesce(%I, '''')), ''A'') ||
+                setweight(to_tsvector(''english'', coalesce(content, '''')), ''B'')
+            ) STORED',
+        title_column
+    );
+END $$;
+
+CREATE INDEX IF NOT EXISTS idx_archives_search ON archives USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_tomes_search ON tomes USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_entries_search ON entries USING GIN (search_vector);
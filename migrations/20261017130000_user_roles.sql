@@ -0,0 +1,5 @@
+-- Administrators may list every account. There is no API for granting the
+-- role; operators set it directly:
+--   UPDATE users SET is_admin = true WHERE username = '...';
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT false;
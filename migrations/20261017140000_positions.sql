@@ -0,0 +1,24 @@
+-- Persistent sidebar order. Tomes are ordered within their archive and
+-- entries within their tome by (position, created_at, id); the server
+-- keeps positions dense when items are moved through the REST API.
+
+ALTER TABLE tomes ADD COLUMN IF NOT EXISTS position INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE entries ADD COLUMN IF NOT EXISTS position INTEGER NOT NULL DEFAULT 0;
+
+-- Existing items keep the order they were shown in so far
+UPDATE tomes SET position = ordered.position
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY archive_id ORDER BY created_at, id) - 1 AS position
+    FROM tomes
+) ordered
+WHERE tomes.id = ordered.id AND tomes.position <> ordered.position;
+
+UPDATE entries SET position = ordered.position
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY tome_id ORDER BY created_at, id) - 1 AS position
+    FROM entries
+) ordered
+WHERE entries.id = ordered.id AND entries.position <> ordered.position;
+
+CREATE INDEX IF NOT EXISTS idx_tomes_archive_position ON tomes(archive_id, position);
+CREATE INDEX IF NOT EXISTS idx_entries_tome_position ON entries(tome_id, position);
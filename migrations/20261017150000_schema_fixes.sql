@@ -0,0 +1,57 @@
+-- Bring databases created from the initial schema in line with the code.
+-- Each step checks the catalog first, so databases that were patched by
+-- hand are left as they are.
+
+-- Entries have always been read and written as `title`
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = 'entries' AND column_name = 'name'
+    ) AND NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = 'entries' AND column_name = 'title'
+    ) THEN
+        ALTER TABLE entries RENAME COLUMN name TO title;
+    END IF;
+END $$;
+
+-- Accounts are updated through PUT /users/:id; existing rows start out
+-- as last changed when they were created
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = 'users' AND column_name = 'updated_at'
+    ) THEN
+        ALTER TABLE users ADD COLUMN updated_at TIMESTAMP;
+        UPDATE users SET updated_at = created_at;
+        ALTER TABLE users
+            ALTER COLUMN updated_at SET NOT NULL,
+            ALTER COLUMN updated_at SET DEFAULT CURRENT_TIMESTAMP;
+    END IF;
+END $$;
+
+-- Accounts without a flag have always been treated as active
+UPDATE users SET is_active = true WHERE is_active IS NULL;
+ALTER TABLE users ALTER COLUMN is_active SET NOT NULL;
+
+-- users.id stays a UUID. The "temp-user-123" placeholder used before
+-- authentication existed never fit the column, so there are no rows to
+-- convert; every id now comes from a session token.
+
+-- With the column name settled, rebuild the entries search vector on
+-- `title`. Databases that ran an earlier search migration may still
+-- maintain it from a trigger, which is dropped here.
+DROP TRIGGER IF EXISTS entries_search_vector ON entries;
+DROP FUNCTION IF EXISTS update_entry_search_vector();
+DROP INDEX IF EXISTS idx_entries_search;
+ALTER TABLE entries DROP COLUMN IF EXISTS search_vector;
+
+ALTER TABLE entries ADD COLUMN search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(content, '')), 'B')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_entries_search ON entries USING GIN (search_vector);
@@ -0,0 +1,31 @@
+-- Only bump a user's revision for rows that are actually written.
+-- An `INSERT ... ON CONFLICT DO UPDATE` fires the BEFORE INSERT trigger
+-- for the proposed row even when it then conflicts, so a stale or
+-- rejected sync push still advanced the counter (and a successful one
+-- advanced it twice). The conflict path fires BEFORE UPDATE only when the
+-- `WHERE` clause lets the update through, so inserts of an id that
+-- already exists now leave the counter to that trigger.
+--
+-- An id inserted concurrently by another transaction is not visible yet;
+-- that rare race still costs one unused revision, which readers skip.
+
+CREATE OR REPLACE FUNCTION bump_sync_revision() RETURNS TRIGGER AS $$
+DECLARE
+    conflicts BOOLEAN;
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        EXECUTE format('SELECT EXISTS (SELECT 1 FROM %I WHERE id = $1)', TG_TABLE_NAME)
+        INTO conflicts
+        USING NEW.id;
+        IF conflicts THEN
+            RETURN NEW;
+        END IF;
+    END IF;
+
+    UPDATE users
+    SET sync_revision = sync_revision + 1
+    WHERE id = NEW.user_id
+    RETURNING sync_revision INTO NEW.revision;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
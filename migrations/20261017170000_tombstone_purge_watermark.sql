@@ -0,0 +1,5 @@
+-- Highest sync revision among each user's purged tombstones. A client
+-- whose cursor is older than this may have missed deletions that no
+-- longer exist to be pulled, so it has to start over with a full pull.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS purged_through BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,179 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+/// Signing material and lifetime for session tokens.
+#[derive(Clone)]
+pub struct AuthKeys {
+    inner: Arc<AuthKeysInner>,
+}
+
+struct AuthKeysInner {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    token_ttl: Duration,
+}
+
+impl AuthKeys {
+    pub fn new(secret: &[u8], token_ttl: Duration) -> Self {
+        Self {
+            inner: Arc::new(AuthKeysInner {
+                encoding: EncodingKey::from_secret(secret),
+                decoding: DecodingKey::from_secret(secret),
+                token_ttl,
+            }),
+        }
+    }
+
+    /// Issues a signed session token for the given user.
+    pub fn issue_token(&self, user_id: Uuid) -> Result<(String, DateTime<Utc>), jsonwebtoken::errors::Error> {
+        let now = Utc::now();
+        let expires_at = now + self.inner.token_ttl;
+        let claims = Claims {
+            sub: user_id,
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.inner.encoding)?;
+        Ok((token, expires_at))
+    }
+
+    fn verify_token(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        let validation = Validation::new(Algorithm::HS256);
+        decode::<Claims>(token, &self.inner.decoding, &validation).map(|data| data.claims)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: Uuid,
+    iat: i64,
+    exp: i64,
+}
+
+/// The authenticated caller, resolved from the `Authorization: Bearer` header.
+/// The token's account must still exist and be active.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthUser {
+    pub user_id: Uuid,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthUser
+where
+    S: Send + Sync,
+    AuthKeys: FromRef<S>,
+    PgPool: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let header = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(ApiError::unauthorized)?;
+        let token = header
+            .strip_prefix("Bearer ")
+            .ok_or_else(ApiError::unauthorized)?;
+
+        let keys = AuthKeys::from_ref(state);
+        let claims = keys
+            .verify_token(token.trim())
+            .map_err(|_| ApiError::unauthorized())?;
+
+        // Tokens outlive deactivation and deletion, so the account is
+        // looked up on every request
+        let is_active: Option<bool> = sqlx::query_scalar!(
+            "SELECT is_active FROM users WHERE id = $1",
+            claims.sub
+        )
+        .fetch_optional(&PgPool::from_ref(state))
+        .await?;
+        if is_active != Some(true) {
+            return Err(ApiError::unauthorized());
+        }
+
+        // Attributes the rest of the request's logs to the caller
+        tracing::Span::current().record("user_id", tracing::field::display(claims.sub));
+        Ok(AuthUser { user_id: claims.sub })
+    }
+}
+
+/// Whether the account exists and has the administrator role.
+pub async fn is_admin(pool: &PgPool, user_id: Uuid) -> Result<bool, ApiError> {
+    let is_admin: Option<bool> = sqlx::query_scalar!(
+        "SELECT is_admin FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(is_admin.unwrap_or(false))
+}
+
+/// Guards a handler so that only callers whose account has the
+/// administrator role get through.
+#[derive(Debug, Clone, Copy)]
+pub struct AdminUser;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminUser
+where
+    S: Send + Sync,
+    AuthKeys: FromRef<S>,
+    PgPool: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AuthUser { user_id } = AuthUser::from_request_parts(parts, state).await?;
+
+        if is_admin(&PgPool::from_ref(state), user_id).await? {
+            Ok(AdminUser)
+        } else {
+            Err(ApiError::forbidden("Only administrators may do this"))
+        }
+    }
+}
+
+/// Hashes a password with argon2 off the async runtime.
+pub async fn hash_password(password: String) -> Result<String, ApiError> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await
+    .map_err(ApiError::internal)?
+    .map_err(ApiError::internal)
+}
+
+/// Checks a password against a stored argon2 hash off the async runtime.
+pub async fn verify_password(password: String, password_hash: String) -> Result<bool, ApiError> {
+    tokio::task::spawn_blocking(move || {
+        let parsed = match PasswordHash::new(&password_hash) {
+            Ok(parsed) => parsed,
+            Err(_) => return false,
+        };
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    })
+    .await
+    .map_err(ApiError::internal)
+}
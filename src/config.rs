@@ -0,0 +1,384 @@
+//! Server configuration.
+//!
+//! Settings are layered: built-in defaults, then the TOML file named by
+//! `CONFIG_FILE` (if set), then environment variables. Every problem found
+//! while loading is collected and reported together at startup rather
+//! than surfacing one at a time as a panic. `config.example.toml` lists
+//! every key with its default and environment variable.
+
+use std::{fmt, net::SocketAddr, str::FromStr, time::Duration};
+
+use axum::http::Uri;
+use serde::Deserialize;
+use sqlx::postgres::PgPoolOptions;
+
+/// Environment variable naming the optional TOML file.
+pub const CONFIG_FILE_VAR: &str = "CONFIG_FILE";
+
+// Upper bounds for settings turned into `chrono::Duration`s, well inside
+// what timestamps can be shifted by without overflowing
+const MAX_TOKEN_TTL_SECONDS: i64 = 365 * 24 * 60 * 60;
+const MAX_TOMBSTONE_RETENTION_DAYS: i64 = 100 * 365;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub cors: CorsConfig,
+    pub log: LogConfig,
+    pub sync: SyncConfig,
+    pub rate_limit: RateLimitConfig,
+    pub features: Features,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address and port to listen on.
+    pub bind: SocketAddr,
+    /// Largest request body accepted, in bytes.
+    pub max_body_bytes: usize,
+    /// Requests still running after this long are answered with a 503.
+    pub request_timeout_seconds: u64,
+    /// Whether responses are gzip/brotli compressed for clients that
+    /// accept it.
+    pub compression: bool,
+    /// How long in-flight requests may run after SIGTERM before their
+    /// connections are dropped.
+    pub shutdown_timeout_seconds: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            max_body_bytes: 2 * 1024 * 1024,
+            request_timeout_seconds: 30,
+            compression: true,
+            shutdown_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a request waits for a free connection before failing.
+    pub acquire_timeout_seconds: u64,
+    /// Idle connections are closed after this long; `0` keeps them open.
+    pub idle_timeout_seconds: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_seconds: 30,
+            idle_timeout_seconds: 600,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_seconds))
+            .idle_timeout((self.idle_timeout_seconds > 0).then(|| Duration::from_secs(self.idle_timeout_seconds)))
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Secret used to sign session tokens.
+    pub jwt_secret: String,
+    pub token_ttl_seconds: i64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: String::new(),
+            token_ttl_seconds: 60 * 60 * 24,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Origins such as `https://app.example.com` that browsers may call
+    /// the API from, or `*` for any. Empty disables CORS.
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per event, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("expected `text` or `json`, got `{}`", value)),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncConfig {
+    /// How long tombstones are kept for clients that have not synced yet.
+    pub tombstone_retention_days: i64,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self { tombstone_retention_days: 90 }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// How many proxies in front of the server append to `X-Forwarded-For`.
+    /// Anonymous callers are keyed by the address that many entries from
+    /// the right, which the outermost trusted proxy wrote, so entries a
+    /// client sends itself are ignored. `0` ignores the header and uses
+    /// the peer address.
+    pub trusted_proxies: usize,
+    pub sync: Budget,
+    pub auth: Budget,
+    pub crud: Budget,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trusted_proxies: 0,
+            sync: Budget { burst: 20, per_minute: 60 },
+            auth: Budget { burst: 10, per_minute: 10 },
+            crud: Budget { burst: 120, per_minute: 600 },
+        }
+    }
+}
+
+/// A token bucket: up to `burst` requests at once, refilled at
+/// `per_minute`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+/// Parts of the service that can be switched off.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    /// Whether anyone may create an account through `/auth/register`.
+    /// Administrators can always add accounts through `POST /users`.
+    pub registration: bool,
+    /// Whether expired tombstones are purged in the background.
+    pub tombstone_purge: bool,
+    /// Whether Prometheus metrics are served on `/metrics`.
+    pub metrics: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self { registration: true, tombstone_purge: true, metrics: true }
+    }
+}
+
+/// Everything wrong with the configuration, one problem per line.
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration")?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads the configuration from `CONFIG_FILE` and the process
+    /// environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = match std::env::var(CONFIG_FILE_VAR) {
+            Ok(path) => Some(
+                std::fs::read_to_string(&path)
+                    .map_err(|e| ConfigError(vec![format!("{} ({}): {}", path, CONFIG_FILE_VAR, e)]))?,
+            ),
+            Err(_) => None,
+        };
+        Self::from_sources(file.as_deref(), |name| std::env::var(name).ok())
+    }
+
+    /// Builds the configuration from TOML text and an environment lookup,
+    /// with the environment taking precedence.
+    pub fn from_sources(toml: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let mut config: Config = match toml {
+            Some(text) => toml::from_str(text).map_err(|e| ConfigError(vec![format!("config file: {}", e)]))?,
+            None => Config::default(),
+        };
+
+        let mut errors = Vec::new();
+        let mut var = |name: &str| env(name).filter(|value| !value.is_empty());
+        override_from(&mut var, "BIND_ADDRESS", &mut config.server.bind, &mut errors);
+        override_from(&mut var, "MAX_BODY_BYTES", &mut config.server.max_body_bytes, &mut errors);
+        override_from(&mut var, "REQUEST_TIMEOUT_SECONDS", &mut config.server.request_timeout_seconds, &mut errors);
+        override_from(&mut var, "COMPRESSION_ENABLED", &mut config.server.compression, &mut errors);
+        override_from(&mut var, "SHUTDOWN_TIMEOUT_SECONDS", &mut config.server.shutdown_timeout_seconds, &mut errors);
+        override_from(&mut var, "DATABASE_URL", &mut config.database.url, &mut errors);
+        override_from(&mut var, "DATABASE_MAX_CONNECTIONS", &mut config.database.max_connections, &mut errors);
+        override_from(&mut var, "DATABASE_MIN_CONNECTIONS", &mut config.database.min_connections, &mut errors);
+        override_from(&mut var, "DATABASE_ACQUIRE_TIMEOUT_SECONDS", &mut config.database.acquire_timeout_seconds, &mut errors);
+        override_from(&mut var, "DATABASE_IDLE_TIMEOUT_SECONDS", &mut config.database.idle_timeout_seconds, &mut errors);
+        override_from(&mut var, "JWT_SECRET", &mut config.auth.jwt_secret, &mut errors);
+        override_from(&mut var, "JWT_TTL_SECONDS", &mut config.auth.token_ttl_seconds, &mut errors);
+        override_from(&mut var, "LOG_FORMAT", &mut config.log.format, &mut errors);
+        override_from(&mut var, "TOMBSTONE_RETENTION_DAYS", &mut config.sync.tombstone_retention_days, &mut errors);
+        override_from(&mut var, "RATE_LIMIT_ENABLED", &mut config.rate_limit.enabled, &mut errors);
+        override_from(&mut var, "RATE_LIMIT_TRUSTED_PROXIES", &mut config.rate_limit.trusted_proxies, &mut errors);
+        override_from(&mut var, "RATE_LIMIT_SYNC_BURST", &mut config.rate_limit.sync.burst, &mut errors);
+        override_from(&mut var, "RATE_LIMIT_SYNC_PER_MINUTE", &mut config.rate_limit.sync.per_minute, &mut errors);
+        override_from(&mut var, "RATE_LIMIT_AUTH_BURST", &mut config.rate_limit.auth.burst, &mut errors);
+        override_from(&mut var, "RATE_LIMIT_AUTH_PER_MINUTE", &mut config.rate_limit.auth.per_minute, &mut errors);
+        override_from(&mut var, "RATE_LIMIT_CRUD_BURST", &mut config.rate_limit.crud.burst, &mut errors);
+        override_from(&mut var, "RATE_LIMIT_CRUD_PER_MINUTE", &mut config.rate_limit.crud.per_minute, &mut errors);
+        override_from(&mut var, "REGISTRATION_ENABLED", &mut config.features.registration, &mut errors);
+        override_from(&mut var, "TOMBSTONE_PURGE_ENABLED", &mut config.features.tombstone_purge, &mut errors);
+        override_from(&mut var, "METRICS_ENABLED", &mut config.features.metrics, &mut errors);
+        if let Some(origins) = var("CORS_ALLOWED_ORIGINS") {
+            config.cors.allowed_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        config.validate(&mut errors);
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(errors))
+        }
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        if self.database.url.is_empty() {
+            errors.push("database.url (DATABASE_URL) must be set".to_string());
+        }
+        if self.database.max_connections == 0 {
+            errors.push("database.max_connections must be at least 1".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            errors.push("database.min_connections must not exceed database.max_connections".to_string());
+        }
+        if self.database.acquire_timeout_seconds == 0 {
+            errors.push("database.acquire_timeout_seconds must be at least 1".to_string());
+        }
+        if self.auth.jwt_secret.is_empty() {
+            errors.push("auth.jwt_secret (JWT_SECRET) must be set".to_string());
+        }
+        if !(1..=MAX_TOKEN_TTL_SECONDS).contains(&self.auth.token_ttl_seconds) {
+            errors.push(format!("auth.token_ttl_seconds must be between 1 and {}", MAX_TOKEN_TTL_SECONDS));
+        }
+        if !(1..=MAX_TOMBSTONE_RETENTION_DAYS).contains(&self.sync.tombstone_retention_days) {
+            errors.push(format!(
+                "sync.tombstone_retention_days must be between 1 and {}",
+                MAX_TOMBSTONE_RETENTION_DAYS
+            ));
+        }
+        if self.server.max_body_bytes == 0 {
+            errors.push("server.max_body_bytes must be at least 1".to_string());
+        }
+        if self.server.request_timeout_seconds == 0 {
+            errors.push("server.request_timeout_seconds must be at least 1".to_string());
+        }
+        let budgets = [
+            ("sync", self.rate_limit.sync),
+            ("auth", self.rate_limit.auth),
+            ("crud", self.rate_limit.crud),
+        ];
+        for (name, budget) in budgets {
+            if budget.burst == 0 || budget.per_minute == 0 {
+                errors.push(format!("rate_limit.{}: burst and per_minute must be at least 1", name));
+            }
+        }
+        for origin in &self.cors.allowed_origins {
+            if !is_valid_origin(origin) {
+                errors.push(format!(
+                    "cors.allowed_origins: `{}` is not `*` or an origin like `https://app.example.com`",
+                    origin
+                ));
+            }
+        }
+    }
+}
+
+// Replaces `target` with the parsed value of the variable, if it is set
+fn override_from<T>(
+    var: &mut impl FnMut(&str) -> Option<String>,
+    name: &str,
+    target: &mut T,
+    errors: &mut Vec<String>,
+) where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    if let Some(value) = var(name) {
+        match value.parse() {
+            Ok(value) => *target = value,
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+    }
+}
+
+// Scheme, host and optional port, with no path, query or trailing slash
+fn is_valid_origin(origin: &str) -> bool {
+    if origin == "*" {
+        return true;
+    }
+    origin.parse::<Uri>().is_ok_and(|uri| {
+        matches!(uri.scheme_str(), Some("http" | "https"))
+            && uri.host().is_some()
+            && uri.path() == "/"
+            && uri.query().is_none()
+            && !origin.ends_with('/')
+    })
+}
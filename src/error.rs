@@ -0,0 +1,138 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::request_id;
+
+// PostgreSQL SQLSTATE codes the API reports as client errors
+const UNIQUE_VIOLATION: &str = "23505";
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+const CHECK_VIOLATION: &str = "23514";
+const NOT_NULL_VIOLATION: &str = "23502";
+const STRING_TOO_LONG: &str = "22001";
+
+/// Error returned by every handler. Renders as
+/// `{"code", "message", "details", "request_id"}` with a matching status.
+///
+/// `code` is a stable, machine-readable identifier; `message` is meant for
+/// humans and may change.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    details: Value,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'static str,
+    message: &'a str,
+    details: &'a Value,
+    request_id: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self { status, code, message: message.into(), details: Value::Null }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad_request", message)
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized", "Missing or invalid credentials")
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "forbidden", message)
+    }
+
+    /// `resource` names what was looked up, e.g. `"archive"`.
+    pub fn not_found(resource: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", format!("The {} does not exist", resource))
+            .with_details(json!({ "resource": resource }))
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, "conflict", message)
+    }
+
+    pub fn precondition_failed() -> Self {
+        Self::new(
+            StatusCode::PRECONDITION_FAILED,
+            "precondition_failed",
+            "The resource has changed since it was fetched",
+        )
+    }
+
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, "unprocessable", message)
+    }
+
+    /// Logs `error` and hides it from the client.
+    pub fn internal(error: impl std::fmt::Display) -> Self {
+        tracing::error!(error = %error, "Internal error");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Something went wrong")
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        let db = match &error {
+            sqlx::Error::RowNotFound => return Self::not_found("record"),
+            sqlx::Error::Database(db) => db,
+            _ => return Self::internal(error),
+        };
+
+        let details = json!({ "constraint": db.constraint() });
+        match db.code().as_deref() {
+            Some(UNIQUE_VIOLATION) => Self::new(
+                StatusCode::CONFLICT,
+                "already_exists",
+                "A record with the same unique value already exists",
+            )
+            .with_details(details),
+            Some(FOREIGN_KEY_VIOLATION) => Self::new(
+                StatusCode::CONFLICT,
+                "reference_conflict",
+                "The record refers to, or is referred to by, another record",
+            )
+            .with_details(details),
+            Some(CHECK_VIOLATION) | Some(NOT_NULL_VIOLATION) => Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "constraint_violation",
+                "The record breaks a database constraint",
+            )
+            .with_details(details),
+            Some(STRING_TOO_LONG) => Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "value_too_long",
+                "A value is longer than the column allows",
+            ),
+            _ => Self::internal(error),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: self.code,
+            message: &self.message,
+            details: &self.details,
+            request_id: request_id::current(),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
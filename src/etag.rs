@@ -0,0 +1,121 @@
+//! Optimistic concurrency for single-resource endpoints.
+//!
+//! Archives, tomes and entries carry a `revision` that the database bumps
+//! on every write (see the sync revision migration). Responses for a single
+//! resource expose it as a strong `ETag`, and writes sent with `If-Match`
+//! only apply while the stored revision is still one of the listed tags.
+//! Writes without `If-Match` keep overwriting unconditionally.
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+use crate::sync::ItemKind;
+
+/// Rows whose current version can be named by an entity tag.
+pub trait Versioned {
+    fn revision(&self) -> i64;
+}
+
+fn format_etag(revision: i64) -> String {
+    format!("\"{}\"", revision)
+}
+
+// Weak tags are accepted too, since proxies may weaken them on the way
+fn parse_etag(tag: &str) -> Option<i64> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix("W/").unwrap_or(tag);
+    tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+}
+
+/// A JSON response carrying the resource's `ETag`.
+pub struct Tagged<T>(pub T);
+
+impl<T: Serialize + Versioned> IntoResponse for Tagged<T> {
+    fn into_response(self) -> Response {
+        let etag = HeaderValue::from_str(&format_etag(self.0.revision()));
+        let mut response = Json(self.0).into_response();
+        if let Ok(etag) = etag {
+            response.headers_mut().insert(header::ETAG, etag);
+        }
+        response
+    }
+}
+
+/// The `If-Match` precondition of a request.
+#[derive(Debug, Clone, Default)]
+pub struct IfMatch(Option<Vec<i64>>);
+
+impl IfMatch {
+    /// The revisions a write may apply to, or `None` when any will do.
+    /// Bind as `$n::bigint[]` and test with
+    /// `($n::bigint[] IS NULL OR revision = ANY($n))`.
+    pub fn revisions(&self) -> Option<&[i64]> {
+        self.0.as_deref()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(header::IF_MATCH) else {
+            return Ok(Self(None));
+        };
+        let value = value
+            .to_str()
+            .map_err(|_| ApiError::bad_request("Invalid If-Match header"))?;
+        if value.trim() == "*" {
+            return Ok(Self(None));
+        }
+        // Tags this server never issued simply cannot match
+        Ok(Self(Some(value.split(',').filter_map(parse_etag).collect())))
+    }
+}
+
+/// Works out why a conditional write to a `kind` row matched no row:
+/// either the row is gone, or `If-Match` named a revision that is no
+/// longer current.
+pub async fn write_failed(pool: &PgPool, kind: ItemKind, id: &str, user_id: Uuid) -> ApiError {
+    let exists = match kind {
+        ItemKind::Archive => sqlx::query_scalar!(
+            "SELECT id FROM archives WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+            id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await,
+        ItemKind::Tome => sqlx::query_scalar!(
+            "SELECT id FROM tomes WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+            id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await,
+        ItemKind::Entry => sqlx::query_scalar!(
+            "SELECT id FROM entries WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+            id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await,
+    };
+
+    match exists {
+        Ok(Some(_)) => ApiError::precondition_failed(),
+        Ok(None) => ApiError::not_found(kind.as_str()),
+        Err(error) => error.into(),
+    }
+}
@@ -0,0 +1,76 @@
+//! Drop-in replacements for axum's `Path`, `Query` and `Bytes` extractors.
+//!
+//! axum answers a rejected extractor with a plain-text body. These wrappers
+//! turn the rejection into an [`ApiError`] so malformed paths, query strings
+//! and bodies get the same JSON error shape as every other failure. JSON
+//! bodies go through [`ValidJson`](crate::validation::ValidJson).
+
+use axum::{
+    async_trait,
+    body::{Bytes, HttpBody},
+    extract::{FromRequest, FromRequestParts},
+    http::{request::Parts, Request},
+    BoxError,
+};
+use serde::de::DeserializeOwned;
+
+use crate::error::ApiError;
+
+/// `axum::extract::Path`, rejecting with an `invalid_path` [`ApiError`].
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) = axum::extract::Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::new(rejection.status(), "invalid_path", rejection.body_text()))?;
+        Ok(Path(value))
+    }
+}
+
+/// `axum::extract::Query`, rejecting with an `invalid_query` [`ApiError`].
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) = axum::extract::Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::new(rejection.status(), "invalid_query", rejection.body_text()))?;
+        Ok(Query(value))
+    }
+}
+
+/// The raw request body, rejecting with an `invalid_body` [`ApiError`].
+pub struct Body(pub Bytes);
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for Body
+where
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = Bytes::from_request(request, state)
+            .await
+            .map_err(|rejection| ApiError::new(rejection.status(), "invalid_body", rejection.body_text()))?;
+        Ok(Body(bytes))
+    }
+}
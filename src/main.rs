@@ -1,77 +1,166 @@
+mod auth;
+mod config;
+mod error;
+mod etag;
+mod extract;
+mod merge;
+mod metrics;
+mod middleware;
 mod models;
+mod ordering;
+mod pagination;
+mod rate_limit;
+mod request_id;
 mod routes;
+mod state;
+mod sync;
+mod tombstones;
+mod validation;
+#[cfg(test)]
+mod tests;
 
 
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::Context;
 use axum::{Router};
-use std::net::SocketAddr;
-use tracing_subscriber;
 use dotenvy::dotenv;
 use axum::Server;
-use sqlx::postgres::PgPoolOptions;
+use sqlx::migrate::Migrator;
 use crate::routes::v1::create_v1_routes;
+use crate::routes::v2::create_v2_routes;
+use crate::auth::AuthKeys;
+use crate::config::{Config, LogFormat};
+use crate::metrics::Metrics;
+use crate::rate_limit::{MemoryStore, RateLimiter};
+use crate::state::AppState;
+
+use crate::error::ApiError;
+
+/// Migrations bundled into the binary, applied at startup.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+async fn not_found_handler() -> ApiError {
+    ApiError::not_found("route")
+}
 
-// Temporary user ID - replace with proper authentication
-const TEMP_USER_ID: &str = "temp-user-123";
-
-use axum::{
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
-};
-use serde_json::json;
-
-async fn not_found_handler() -> impl IntoResponse {
-    let body = Json(json!({
-        "error": "Route not found",
-        "status": 404
-    }));
-    (StatusCode::NOT_FOUND, body)
+fn build_app(state: AppState, config: &Config) -> Router {
+    let metrics = state.metrics.clone();
+    let mut routes = Router::new().merge(routes::health::routes(state.clone()));
+    if config.features.metrics {
+        routes = routes.merge(routes::metrics::routes(state.clone()));
+    }
+    let routes = routes
+        .nest("/api/v1", create_v1_routes(state.clone()))
+        .nest("/api/v2", create_v2_routes(state))
+        .fallback(not_found_handler);
+
+    middleware::apply(routes, &config.server, &config.cors, metrics)
 }
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     dotenv().ok();
-    tracing_subscriber::fmt::init();  
-    // Load the database URL from environment variables
-    let db_url = std::env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set in the environment variables");
+    let config = Config::load()?;
+    match config.log.format {
+        LogFormat::Text => tracing_subscriber::fmt().init(),
+        LogFormat::Json => tracing_subscriber::fmt().json().init(),
+    }
+
     // Create a connection pool for the PostgreSQL database
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&db_url)
+    let pool = config
+        .database
+        .pool_options()
+        .connect(&config.database.url)
         .await
-        .expect("Failed to connect to the database");
-
-    println!("🔄 Running database migrations...");
-    sqlx::migrate!("./migrations").run(&pool).await?;
-    println!("✅ Migrations completed");
-
-    let _ = sqlx::query(
-        "INSERT INTO users (id, username, email, password_hash) 
-         VALUES ($1, $2, $3, $4) 
-         ON CONFLICT (id) DO NOTHING"
-    ).bind(TEMP_USER_ID)
-     .bind("test_user")
-     .bind("testuser@example.com")
-     .bind("hashed_password")
-     .execute(&pool)
-     .await;
-
-    let app = Router::new()
-        .nest("/api/v1", create_v1_routes(pool.clone()))
-        .fallback(not_found_handler);
+        .context("Failed to connect to the database")?;
+
+    tracing::info!("Running database migrations");
+    MIGRATOR.run(&pool).await.context("Failed to run database migrations")?;
+    tracing::info!("Migrations completed");
 
-    // Define the address to listen on
-    // You can change the port or address as needed
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
-    println!("Listening on http://{}", addr);
+    if config.features.tombstone_purge {
+        tombstones::spawn_purge_task(pool.clone(), chrono::Duration::days(config.sync.tombstone_retention_days));
+    }
+
+    let state = AppState {
+        pool: pool.clone(),
+        auth: AuthKeys::new(
+            config.auth.jwt_secret.as_bytes(),
+            chrono::Duration::seconds(config.auth.token_ttl_seconds),
+        ),
+        features: config.features,
+        metrics: Metrics::new(),
+        rate_limiter: RateLimiter::new(config.rate_limit.clone(), Arc::new(MemoryStore::default())),
+    };
+
+    let app = build_app(state, &config);
+
+    let addr = config.server.bind;
 
     // Start the server
-    tracing::info!("Starting server on {}", addr);
-    Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await
-        .expect("Failed to start the server");
+    let (begin_shutdown, shutdown_requested) = tokio::sync::oneshot::channel::<()>();
+    let server = Server::try_bind(&addr)
+        .with_context(|| format!("Failed to bind {}", addr))?
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async {
+            shutdown_requested.await.ok();
+        });
+    tokio::pin!(server);
+    tracing::info!(%addr, "Listening");
+
+    // On SIGTERM or Ctrl-C stop accepting connections and give in-flight
+    // requests, such as a sync push mid-transaction, time to finish
+    let deadline = tokio::select! {
+        result = &mut server => {
+            result.context("Server error")?;
+            None
+        }
+        _ = shutdown_signal() => {
+            let timeout = Duration::from_secs(config.server.shutdown_timeout_seconds);
+            let deadline = tokio::time::Instant::now() + timeout;
+            tracing::info!(?timeout, "Shutting down, draining connections");
+            begin_shutdown.send(()).ok();
+            match tokio::time::timeout_at(deadline, &mut server).await {
+                Ok(result) => result.context("Server error")?,
+                Err(_) => tracing::warn!("Shutdown timeout elapsed, dropping remaining connections"),
+            }
+            Some(deadline)
+        }
+    };
 
+    // Closing waits for checked-out connections, which requests abandoned
+    // at the deadline may still hold, so it gets no more time than they did
+    match deadline {
+        Some(deadline) => {
+            if tokio::time::timeout_at(deadline, pool.close()).await.is_err() {
+                tracing::warn!("Shutdown timeout elapsed, abandoning database connections");
+            }
+        }
+        None => pool.close().await,
+    }
     Ok(())
 }
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
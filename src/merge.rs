@@ -0,0 +1,140 @@
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// Result of merging two edits of the same text against a common base.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MergeOutcome {
+    /// Both edits could be combined without overlapping.
+    Clean(String),
+    /// Both sides changed the same region in different ways.
+    Conflict,
+}
+
+/// Line-based three-way merge in the style of diff3.
+///
+/// Lines that are unchanged on both sides act as anchors. Between two
+/// anchors, a region changed on only one side takes that side's version, a
+/// region changed identically on both sides is kept once, and anything else
+/// is a conflict.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> MergeOutcome {
+    if ours == theirs || theirs == base {
+        return MergeOutcome::Clean(ours.to_string());
+    }
+    if ours == base {
+        return MergeOutcome::Clean(theirs.to_string());
+    }
+
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    let to_ours = matching_lines(&base, &ours);
+    let to_theirs = matching_lines(&base, &theirs);
+
+    let mut merged = String::new();
+    let (mut b, mut o, mut t) = (0, 0, 0);
+
+    loop {
+        // Next base line that survives unchanged on both sides
+        let anchor = (b..base.len()).find_map(|line| match (to_ours[line], to_theirs[line]) {
+            (Some(in_ours), Some(in_theirs)) if in_ours >= o && in_theirs >= t => {
+                Some((line, in_ours, in_theirs))
+            }
+            _ => None,
+        });
+        let (b_end, o_end, t_end) = anchor.unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        let base_chunk = &base[b..b_end];
+        let ours_chunk = &ours[o..o_end];
+        let theirs_chunk = &theirs[t..t_end];
+
+        let chosen = if ours_chunk == base_chunk {
+            theirs_chunk
+        } else if theirs_chunk == base_chunk || ours_chunk == theirs_chunk {
+            ours_chunk
+        } else {
+            return MergeOutcome::Conflict;
+        };
+        merged.extend(chosen.iter().copied());
+
+        match anchor {
+            Some((line, in_ours, in_theirs)) => {
+                merged.push_str(base[line]);
+                b = line + 1;
+                o = in_ours + 1;
+                t = in_theirs + 1;
+            }
+            None => break,
+        }
+    }
+
+    MergeOutcome::Clean(merged)
+}
+
+/// For every line of `old`, the index of the line it is matched with in
+/// `new`, if it survived.
+fn matching_lines(old: &[&str], new: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; old.len()];
+    for op in capture_diff_slices(Algorithm::Myers, old, new) {
+        if let DiffOp::Equal { old_index, new_index, len } = op {
+            for offset in 0..len {
+                matches[old_index + offset] = Some(new_index + offset);
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge3, MergeOutcome};
+
+    fn clean(text: &str) -> MergeOutcome {
+        MergeOutcome::Clean(text.to_string())
+    }
+
+    #[test]
+    fn one_sided_edits_win() {
+        let base = "one\ntwo\nthree\n";
+        let edited = "one\n2\nthree\n";
+
+        assert_eq!(merge3(base, base, edited), clean(edited));
+        assert_eq!(merge3(base, edited, base), clean(edited));
+        assert_eq!(merge3(base, edited, edited), clean(edited));
+    }
+
+    #[test]
+    fn non_overlapping_edits_are_combined() {
+        let base = "intro\n\nmiddle\n\noutro\n";
+        let ours = "a better intro\n\nmiddle\n\noutro\n";
+        let theirs = "intro\n\nmiddle\n\noutro\nand a postscript\n";
+
+        assert_eq!(
+            merge3(base, ours, theirs),
+            clean("a better intro\n\nmiddle\n\noutro\nand a postscript\n")
+        );
+    }
+
+    #[test]
+    fn deletions_merge_with_edits_elsewhere() {
+        let base = "keep\ndrop\nkeep too\nchange\n";
+        let ours = "keep\nkeep too\nchange\n";
+        let theirs = "keep\ndrop\nkeep too\nchanged\n";
+
+        assert_eq!(merge3(base, ours, theirs), clean("keep\nkeep too\nchanged\n"));
+    }
+
+    #[test]
+    fn overlapping_edits_conflict() {
+        let base = "one\ntwo\nthree\n";
+
+        assert_eq!(merge3(base, "one\nTWO\nthree\n", "one\nzwei\nthree\n"), MergeOutcome::Conflict);
+        assert_eq!(merge3("title", "mine", "yours"), MergeOutcome::Conflict);
+    }
+
+    #[test]
+    fn competing_inserts_at_the_same_spot_conflict() {
+        let base = "a\nb\n";
+
+        assert_eq!(merge3(base, "a\nx\nb\n", "a\ny\nb\n"), MergeOutcome::Conflict);
+    }
+}
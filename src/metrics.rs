@@ -0,0 +1,201 @@
+//! Prometheus metrics, served in the text format on `/metrics`.
+//!
+//! Every series is prefixed with `stackscribe_`. Request metrics are
+//! labelled with the route pattern (e.g. `/api/v1/archives/:id`) rather
+//! than the path, so ids never end up as label values.
+
+use std::time::Duration;
+
+use prometheus::{
+    core::Collector, exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+use crate::sync::{ItemKind, SyncItemResult, SyncItemStatus, SyncPushResults, SyncResponse};
+
+const NAMESPACE: &str = "stackscribe";
+
+/// Handle to the metrics registry. Cloning it is cheap and every clone
+/// records into the same series.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    db_connections: IntGaugeVec,
+    db_max_connections: IntGauge,
+    sync_pulled_rows: IntCounterVec,
+    sync_pushed_rows: IntCounterVec,
+    sync_stale_rows: IntCounterVec,
+    sync_payload_bytes: HistogramVec,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let opts = |name: &str, help: &str| Opts::new(name, help).namespace(NAMESPACE);
+
+        let http_requests = register(
+            &registry,
+            IntCounterVec::new(
+                opts("http_requests_total", "HTTP requests handled, by route and status"),
+                &["method", "route", "status"],
+            ),
+        );
+        let http_request_duration = register(
+            &registry,
+            HistogramVec::new(
+                HistogramOpts::from(opts("http_request_duration_seconds", "Time taken to answer HTTP requests")),
+                &["method", "route"],
+            ),
+        );
+        let db_connections = register(
+            &registry,
+            IntGaugeVec::new(
+                opts("db_pool_connections", "Open database connections, by whether they are in use"),
+                &["state"],
+            ),
+        );
+        let db_max_connections = register(
+            &registry,
+            IntGauge::with_opts(opts("db_pool_max_connections", "Most connections the pool will open")),
+        );
+        let sync_pulled_rows = register(
+            &registry,
+            IntCounterVec::new(
+                opts("sync_pulled_rows_total", "Rows sent to clients by GET /sync"),
+                &["kind", "change"],
+            ),
+        );
+        let sync_pushed_rows = register(
+            &registry,
+            IntCounterVec::new(
+                opts("sync_pushed_rows_total", "Rows received from clients by POST /sync, by outcome"),
+                &["kind", "change", "status"],
+            ),
+        );
+        let sync_stale_rows = register(
+            &registry,
+            IntCounterVec::new(
+                opts(
+                    "sync_stale_rows_total",
+                    "Pushed rows skipped because the server already had a newer version",
+                ),
+                &["kind"],
+            ),
+        );
+        let sync_payload_bytes = register(
+            &registry,
+            HistogramVec::new(
+                // 1 KiB up to 16 MiB
+                HistogramOpts::from(opts("sync_payload_bytes", "Size of sync request and response bodies"))
+                    .buckets(exponential_buckets(1024.0, 4.0, 8).expect("bucket layout is valid")),
+                &["direction"],
+            ),
+        );
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            db_connections,
+            db_max_connections,
+            sync_pulled_rows,
+            sync_pushed_rows,
+            sync_stale_rows,
+            sync_payload_bytes,
+        }
+    }
+
+    /// Counts a finished request against its route pattern.
+    pub fn record_request(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Counts the rows in a page of changes sent to a client.
+    pub fn record_pull(&self, response: &SyncResponse) {
+        let rows = [
+            (ItemKind::Archive, "upsert", response.archives.len()),
+            (ItemKind::Tome, "upsert", response.tomes.len()),
+            (ItemKind::Entry, "upsert", response.entries.len()),
+            (ItemKind::Archive, "delete", response.deleted.archives.len()),
+            (ItemKind::Tome, "delete", response.deleted.tomes.len()),
+            (ItemKind::Entry, "delete", response.deleted.entries.len()),
+        ];
+        for (kind, change, count) in rows {
+            self.sync_pulled_rows
+                .with_label_values(&[kind.as_str(), change])
+                .inc_by(count as u64);
+        }
+    }
+
+    /// Counts the pushed rows by outcome, including those skipped as stale.
+    pub fn record_push(&self, results: &SyncPushResults) {
+        self.record_pushed(ItemKind::Archive, "upsert", &results.archives);
+        self.record_pushed(ItemKind::Tome, "upsert", &results.tomes);
+        self.record_pushed(ItemKind::Entry, "upsert", &results.entries);
+        self.record_pushed(ItemKind::Archive, "delete", &results.deleted.archives);
+        self.record_pushed(ItemKind::Tome, "delete", &results.deleted.tomes);
+        self.record_pushed(ItemKind::Entry, "delete", &results.deleted.entries);
+    }
+
+    fn record_pushed<T>(&self, kind: ItemKind, change: &str, results: &[SyncItemResult<T>]) {
+        for result in results {
+            self.sync_pushed_rows
+                .with_label_values(&[kind.as_str(), change, result.status.as_str()])
+                .inc();
+            if matches!(result.status, SyncItemStatus::Stale) {
+                self.sync_stale_rows.with_label_values(&[kind.as_str()]).inc();
+            }
+        }
+    }
+
+    /// Records the size of a sync body; `direction` is `pull` or `push`.
+    pub fn record_sync_payload(&self, direction: &str, bytes: usize) {
+        self.sync_payload_bytes
+            .with_label_values(&[direction])
+            .observe(bytes as f64);
+    }
+
+    /// Renders every series in the Prometheus text format, sampling the
+    /// pool's connection usage first.
+    pub fn render(&self, pool: &PgPool) -> String {
+        let open = pool.size() as i64;
+        let idle = pool.num_idle() as i64;
+        self.db_connections.with_label_values(&["idle"]).set(idle);
+        self.db_connections.with_label_values(&["in_use"]).set(open - idle);
+        self.db_max_connections
+            .set(pool.options().get_max_connections() as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("the text encoder writes to memory");
+        String::from_utf8(buffer).expect("the text format is UTF-8")
+    }
+}
+
+// The metric definitions are fixed, so failing to create or register one is
+// a bug rather than something to recover from
+fn register<M>(registry: &Registry, metric: prometheus::Result<M>) -> M
+where
+    M: Collector + Clone + 'static,
+{
+    let metric = metric.expect("metric definition is valid");
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("metric is registered once");
+    metric
+}
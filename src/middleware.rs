@@ -0,0 +1,142 @@
+//! The HTTP middleware stack wrapped around every route.
+//!
+//! From the outside in: request ids, a tracing span and metrics per
+//! request, CORS, response compression, a per-request timeout and the
+//! request body limit. Each is driven by
+//! [`ServerConfig`] and [`CorsConfig`]; CORS is left off entirely when no
+//! origins are allowed.
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{DefaultBodyLimit, MatchedPath, State},
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tracing::{field, Instrument};
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+};
+
+use crate::config::{CorsConfig, ServerConfig};
+use crate::error::ApiError;
+use crate::metrics::Metrics;
+use crate::pagination::NEXT_CURSOR_HEADER;
+use crate::request_id::{self, REQUEST_ID_HEADER};
+
+// Browsers may cache a preflight response for this long
+const CORS_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+pub fn apply(router: Router, server: &ServerConfig, cors: &CorsConfig, metrics: Metrics) -> Router {
+    let mut router = router
+        .layer(DefaultBodyLimit::max(server.max_body_bytes))
+        .layer(middleware::from_fn_with_state(
+            Duration::from_secs(server.request_timeout_seconds),
+            timeout,
+        ));
+
+    if server.compression {
+        router = router.layer(CompressionLayer::new());
+    }
+    if let Some(cors) = cors_layer(cors) {
+        router = router.layer(cors);
+    }
+
+    router
+        .layer(middleware::from_fn_with_state(metrics, trace))
+        .layer(middleware::from_fn(request_id::propagate))
+}
+
+fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+    if config.allowed_origins.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        // Origins were validated when the configuration was loaded
+        AllowOrigin::list(
+            config
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+            .allow_headers([
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                header::IF_MATCH,
+                REQUEST_ID_HEADER,
+            ])
+            .expose_headers([
+                header::ETAG,
+                header::LINK,
+                header::RETRY_AFTER,
+                NEXT_CURSOR_HEADER,
+                REQUEST_ID_HEADER,
+            ])
+            .max_age(CORS_MAX_AGE),
+    )
+}
+
+/// Fails requests that run longer than the configured limit. The handler
+/// is dropped, so an open transaction is rolled back rather than committed
+/// after the client has given up.
+async fn timeout<B>(State(limit): State<Duration>, request: Request<B>, next: Next<B>) -> Response {
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "timeout",
+            "The request took too long to process",
+        )
+        .into_response(),
+    }
+}
+
+/// Runs the request inside a span carrying its method, route and id, plus
+/// the caller once authenticated, and logs and counts its status and
+/// latency when it completes.
+async fn trace<B>(State(metrics): State<Metrics>, request: Request<B>, next: Next<B>) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().clone();
+    let span = tracing::info_span!(
+        "request",
+        method = %method,
+        route,
+        request_id = request_id::current().as_deref(),
+        user_id = field::Empty,
+        status = field::Empty,
+        latency_ms = field::Empty,
+    );
+
+    let started = Instant::now();
+    let response = next.run(request).instrument(span.clone()).await;
+    let status = response.status();
+    let latency = started.elapsed();
+    span.record("status", status.as_u16());
+    span.record("latency_ms", latency.as_millis() as u64);
+    metrics.record_request(method.as_str(), &route, status.as_u16(), latency);
+
+    span.in_scope(|| {
+        if status.is_server_error() {
+            tracing::error!("Request failed");
+        } else {
+            tracing::info!("Request completed");
+        }
+    });
+    response
+}
@@ -1,13 +1,45 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use chrono::{NaiveDateTime};
+use uuid::Uuid;
+use crate::etag::Versioned;
+use crate::pagination::Paginated;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Archive {
     pub id: String,
-    pub user_id: String,
+    pub user_id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
-}
\ No newline at end of file
+    /// Bumped by the database on every write; doubles as the `ETag`.
+    #[serde(default)]
+    pub revision: i64,
+}
+
+impl Paginated for Archive {
+    const COLUMNS: &'static str = "id, user_id, name, description, created_at, updated_at, revision";
+
+    fn cursor_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Versioned for Archive {
+    fn revision(&self) -> i64 {
+        self.revision
+    }
+}
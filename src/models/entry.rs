@@ -1,14 +1,49 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use chrono::{NaiveDateTime};
+use uuid::Uuid;
+use crate::etag::Versioned;
+use crate::pagination::Paginated;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Entry {
     pub id: String,
     pub tome_id: String,
-    pub user_id: String,
+    pub user_id: Uuid,
     pub title: String,
     pub content: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
-}
\ No newline at end of file
+    /// Order among the other children of the same tome.
+    #[serde(default)]
+    pub position: i32,
+    /// Bumped by the database on every write; doubles as the `ETag`.
+    #[serde(default)]
+    pub revision: i64,
+}
+
+impl Paginated for Entry {
+    const COLUMNS: &'static str = "id, tome_id, user_id, title, content, created_at, updated_at, position, revision";
+
+    fn cursor_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn name(&self) -> &str {
+        &self.title
+    }
+
+    fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Versioned for Entry {
+    fn revision(&self) -> i64 {
+        self.revision
+    }
+}
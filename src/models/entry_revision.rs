@@ -0,0 +1,32 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EntryRevision {
+    pub entry_id: String,
+    pub revision: i64,
+    pub title: String,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// Listing form of a revision, without the full content.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EntryRevisionSummary {
+    pub revision: i64,
+    pub title: String,
+    pub content_length: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryRevisionDiff {
+    pub entry_id: String,
+    pub from: i64,
+    pub to: i64,
+    pub from_title: String,
+    pub to_title: String,
+    /// Line-based unified diff of the content.
+    pub diff: String,
+}
@@ -2,4 +2,6 @@ pub mod user;
 pub mod archive;
 pub mod tome;
 pub mod entry;
-pub mod sync;
\ No newline at end of file
+pub mod entry_revision;
+pub mod search;
+pub mod tree;
@@ -0,0 +1,22 @@
+use serde::Serialize;
+
+/// One level of the archive/tome hierarchy above a search hit.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchPathSegment {
+    pub kind: &'static str,
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    /// `archive`, `tome` or `entry`.
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    /// Matching excerpt with terms wrapped in `<mark>` tags.
+    pub snippet: String,
+    pub rank: f32,
+    /// Containing archive and tome, outermost first.
+    pub path: Vec<SearchPathSegment>,
+}
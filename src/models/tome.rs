@@ -1,14 +1,49 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use chrono::{NaiveDateTime};
+use uuid::Uuid;
+use crate::etag::Versioned;
+use crate::pagination::Paginated;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Tome {
     pub id: String,
     pub archive_id: String,
-    pub user_id: String,
+    pub user_id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
-}   
\ No newline at end of file
+    /// Order among the other children of the same archive.
+    #[serde(default)]
+    pub position: i32,
+    /// Bumped by the database on every write; doubles as the `ETag`.
+    #[serde(default)]
+    pub revision: i64,
+}
+
+impl Paginated for Tome {
+    const COLUMNS: &'static str = "id, archive_id, user_id, name, description, created_at, updated_at, position, revision";
+
+    fn cursor_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
+
+impl Versioned for Tome {
+    fn revision(&self) -> i64 {
+        self.revision
+    }
+}
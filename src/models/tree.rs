@@ -0,0 +1,31 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+use crate::models::archive::Archive;
+use crate::models::tome::Tome;
+
+/// An entry without its content, for navigation.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EntrySummary {
+    pub id: String,
+    pub tome_id: String,
+    pub title: String,
+    pub position: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TomeTree {
+    #[serde(flatten)]
+    pub tome: Tome,
+    pub entries: Vec<EntrySummary>,
+}
+
+/// An archive with everything beneath it, as shown in the sidebar.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveTree {
+    #[serde(flatten)]
+    pub archive: Archive,
+    pub tomes: Vec<TomeTree>,
+}
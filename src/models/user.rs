@@ -1,15 +1,47 @@
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use sqlx::FromRow;
 use chrono::{NaiveDateTime};
+use uuid::Uuid;
+use crate::pagination::Paginated;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct User {
-    pub id: String,
+/// The representation of an account the API hands out; the password hash
+/// is never read into it. `email` is only filled in for the account owner
+/// and administrators.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UserProfile {
+    pub id: Uuid,
     pub username: String,
-    pub email: String,
-    pub password_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
-    pub last_login: Option<NaiveDateTime>,
     pub is_active: bool,
-}
\ No newline at end of file
+    pub is_admin: bool,
+}
+
+impl UserProfile {
+    /// Strips what only the owner may see.
+    pub fn public(self) -> Self {
+        Self { email: None, ..self }
+    }
+}
+
+impl Paginated for UserProfile {
+    const COLUMNS: &'static str = "id, username, email, created_at, updated_at, is_active, is_admin";
+
+    fn cursor_id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn name(&self) -> &str {
+        &self.username
+    }
+
+    fn created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
+
+    fn updated_at(&self) -> NaiveDateTime {
+        self.updated_at
+    }
+}
@@ -0,0 +1,185 @@
+//! Sidebar order of tomes within their archive and entries within their
+//! tome.
+//!
+//! Children are listed by `(position, created_at, id)`. New items are
+//! appended after the last live sibling; moves through the REST API
+//! renumber the children of the parent moved into, so positions there
+//! stay dense. Every renumbered row gets a new revision, so other devices
+//! pick up the new order through `/sync`. Positions pushed by sync clients
+//! are stored as given; ties fall back to creation order.
+//!
+//! Requested positions range from 0 to [`MAX_POSITION`], and appending
+//! saturates there, so computing the next position never overflows.
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+/// Largest position a client may request. Items appended after a sibling
+/// at or past it take that sibling's position and sort by creation order.
+pub const MAX_POSITION: i32 = 1_000_000;
+
+/// A kind of item ordered within a parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Siblings {
+    /// Tomes within an archive.
+    Tomes,
+    /// Entries within a tome.
+    Entries,
+}
+
+impl Siblings {
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Tomes => "tome",
+            Self::Entries => "entry",
+        }
+    }
+
+    fn parent_resource(self) -> &'static str {
+        match self {
+            Self::Tomes => "archive",
+            Self::Entries => "tome",
+        }
+    }
+}
+
+/// Moves an item under `parent_id` (its current parent when `None`) to
+/// `position` among the live children there, or to the end when
+/// `position` is `None` or past the end.
+///
+/// Both the item and the target parent must belong to the caller. When
+/// `if_match` lists revisions, the item must still be at one of them.
+pub async fn move_item(
+    conn: &mut PgConnection,
+    siblings: Siblings,
+    user_id: Uuid,
+    id: &str,
+    parent_id: Option<&str>,
+    position: Option<i32>,
+    if_match: Option<&[i64]>,
+) -> Result<(), ApiError> {
+    let item = match siblings {
+        Siblings::Tomes => sqlx::query!(
+            "SELECT archive_id AS parent_id, revision FROM tomes WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE",
+            id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .map(|row| (row.parent_id, row.revision)),
+        Siblings::Entries => sqlx::query!(
+            "SELECT tome_id AS parent_id, revision FROM entries WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE",
+            id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .map(|row| (row.parent_id, row.revision)),
+    };
+    let (current_parent_id, revision) = item.ok_or(ApiError::not_found(siblings.resource()))?;
+
+    if let Some(revisions) = if_match
+        && !revisions.contains(&revision)
+    {
+        return Err(ApiError::precondition_failed());
+    }
+
+    let parent_id = parent_id.map_or(current_parent_id, str::to_string);
+
+    // Locking the parent serializes concurrent reorders of its children
+    let parent = match siblings {
+        Siblings::Tomes => sqlx::query_scalar!(
+            "SELECT id FROM archives WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE",
+            parent_id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        Siblings::Entries => sqlx::query_scalar!(
+            "SELECT id FROM tomes WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL FOR UPDATE",
+            parent_id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+    };
+    parent.ok_or(ApiError::not_found(siblings.parent_resource()))?;
+
+    let mut order = match siblings {
+        Siblings::Tomes => sqlx::query_scalar!(
+            "SELECT id FROM tomes WHERE archive_id = $1 AND user_id = $2 AND deleted_at IS NULL AND id <> $3 ORDER BY position, created_at, id",
+            parent_id,
+            user_id,
+            id
+        )
+        .fetch_all(&mut *conn)
+        .await?,
+        Siblings::Entries => sqlx::query_scalar!(
+            "SELECT id FROM entries WHERE tome_id = $1 AND user_id = $2 AND deleted_at IS NULL AND id <> $3 ORDER BY position, created_at, id",
+            parent_id,
+            user_id,
+            id
+        )
+        .fetch_all(&mut *conn)
+        .await?,
+    };
+
+    let index = position.map_or(order.len(), |position| (position.max(0) as usize).min(order.len()));
+    order.insert(index, id.to_string());
+
+    match siblings {
+        Siblings::Tomes => sqlx::query!(
+            "UPDATE tomes SET archive_id = $3, position = $4, updated_at = CURRENT_TIMESTAMP WHERE id = $1 AND user_id = $2",
+            id,
+            user_id,
+            parent_id,
+            index as i32
+        )
+        .execute(&mut *conn)
+        .await?,
+        Siblings::Entries => sqlx::query!(
+            "UPDATE entries SET tome_id = $3, position = $4, updated_at = CURRENT_TIMESTAMP WHERE id = $1 AND user_id = $2",
+            id,
+            user_id,
+            parent_id,
+            index as i32
+        )
+        .execute(&mut *conn)
+        .await?,
+    };
+
+    let (ids, positions): (Vec<String>, Vec<i32>) = order
+        .into_iter()
+        .enumerate()
+        .filter(|(_, sibling)| sibling != id)
+        .map(|(position, sibling)| (sibling, position as i32))
+        .unzip();
+
+    // Only siblings whose position actually changes get a new revision
+    match siblings {
+        Siblings::Tomes => sqlx::query!(
+            "UPDATE tomes SET position = v.position, updated_at = CURRENT_TIMESTAMP
+             FROM UNNEST($1::text[], $2::int[]) AS v(id, position)
+             WHERE tomes.id = v.id AND tomes.user_id = $3 AND tomes.position <> v.position",
+            &ids,
+            &positions,
+            user_id
+        )
+        .execute(&mut *conn)
+        .await?,
+        Siblings::Entries => sqlx::query!(
+            "UPDATE entries SET position = v.position, updated_at = CURRENT_TIMESTAMP
+             FROM UNNEST($1::text[], $2::int[]) AS v(id, position)
+             WHERE entries.id = v.id AND entries.user_id = $3 AND entries.position <> v.position",
+            &ids,
+            &positions,
+            user_id
+        )
+        .execute(&mut *conn)
+        .await?,
+    };
+
+    Ok(())
+}
@@ -0,0 +1,249 @@
+//! Keyset pagination, sorting and `updated_since` filtering shared by the
+//! list endpoints.
+//!
+//! Pages are cut with a cursor that remembers the sort key and id of the
+//! last row returned, so rows inserted or deleted between requests never
+//! shift later pages. The next page is advertised both in a `Link` header
+//! (`rel="next"`) and in `X-Next-Cursor`.
+
+use axum::{
+    http::{header, HeaderName, HeaderValue, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::error::ApiError;
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+pub const MAX_PAGE_LIMIT: i64 = 200;
+
+pub const NEXT_CURSOR_HEADER: HeaderName = HeaderName::from_static("x-next-cursor");
+
+/// Query parameters understood by every list endpoint.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListParams {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    /// `name`, `created_at` or `updated_at`; prefix with `-` to sort
+    /// descending. Defaults to `created_at`.
+    pub sort: Option<String>,
+    /// Only rows changed after this RFC 3339 timestamp.
+    pub updated_since: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Rows that can be listed page by page.
+pub trait Paginated {
+    /// Columns to select when listing, naming every field of the row once.
+    const COLUMNS: &'static str;
+
+    fn cursor_id(&self) -> String;
+    fn name(&self) -> &str;
+    fn created_at(&self) -> NaiveDateTime;
+    fn updated_at(&self) -> NaiveDateTime;
+}
+
+/// Columns a resource maps the generic sort fields onto.
+pub struct Columns {
+    /// Column sorted on for `sort=name`.
+    pub name: &'static str,
+    /// Tie-breaker, compared as text.
+    pub id: &'static str,
+}
+
+impl Columns {
+    pub const DEFAULT: Self = Self { name: "name", id: "id" };
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    sort: SortField,
+    desc: bool,
+    value: String,
+    id: String,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        // Serializing a plain struct of strings cannot fail
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(token: &str) -> Option<Self> {
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(token).ok()?).ok()
+    }
+}
+
+/// A validated list request.
+#[derive(Debug)]
+pub struct ListRequest {
+    limit: i64,
+    sort: SortField,
+    desc: bool,
+    after: Option<Cursor>,
+    updated_since: Option<NaiveDateTime>,
+}
+
+impl ListParams {
+    pub fn resolve(self) -> Result<ListRequest, ApiError> {
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(ApiError::bad_request(format!("`limit` must be between 1 and {}", MAX_PAGE_LIMIT)));
+        }
+
+        let sort = self.sort.as_deref().unwrap_or("created_at");
+        let (desc, field) = match sort.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, sort),
+        };
+        let sort = match field {
+            "name" => SortField::Name,
+            "created_at" => SortField::CreatedAt,
+            "updated_at" => SortField::UpdatedAt,
+            _ => {
+                return Err(ApiError::bad_request(
+                    "`sort` must be one of name, created_at or updated_at, optionally prefixed with -",
+                ));
+            }
+        };
+
+        let after = match self.cursor.as_deref().filter(|token| !token.is_empty()) {
+            Some(token) => {
+                let cursor = Cursor::decode(token)
+                    .filter(|cursor| cursor.sort == sort && cursor.desc == desc)
+                    .ok_or_else(|| ApiError::bad_request("Invalid cursor for this sort order"))?;
+                if sort != SortField::Name && parse_timestamp(&cursor.value).is_none() {
+                    return Err(ApiError::bad_request("Invalid cursor for this sort order"));
+                }
+                Some(cursor)
+            }
+            None => None,
+        };
+
+        let updated_since = match self.updated_since.as_deref() {
+            Some(value) => Some(
+                parse_timestamp(value)
+                    .ok_or_else(|| ApiError::bad_request("`updated_since` must be an RFC 3339 timestamp"))?,
+            ),
+            None => None,
+        };
+
+        Ok(ListRequest { limit, sort, desc, after, updated_since })
+    }
+}
+
+impl ListRequest {
+    /// Appends the filter, keyset condition, ordering and limit to a query
+    /// whose `WHERE` clause has already been started.
+    pub fn apply(&self, query: &mut QueryBuilder<'_, Postgres>, columns: &Columns) {
+        let sort_column = match self.sort {
+            SortField::Name => columns.name,
+            SortField::CreatedAt => "created_at",
+            SortField::UpdatedAt => "updated_at",
+        };
+
+        if let Some(updated_since) = self.updated_since {
+            query.push(" AND updated_at > ").push_bind(updated_since);
+        }
+
+        if let Some(after) = &self.after {
+            let op = if self.desc { "<" } else { ">" };
+            query.push(format_args!(" AND ({}, {}::text) {} (", sort_column, columns.id, op));
+            match self.sort {
+                SortField::Name => query.push_bind(after.value.clone()),
+                // Checked in `resolve`
+                _ => query.push_bind(parse_timestamp(&after.value)),
+            };
+            query.push(", ").push_bind(after.id.clone()).push(")");
+        }
+
+        let direction = if self.desc { "DESC" } else { "ASC" };
+        query.push(format_args!(
+            " ORDER BY {} {}, {}::text {} LIMIT ",
+            sort_column, direction, columns.id, direction
+        ));
+        // One extra row tells whether another page follows
+        query.push_bind(self.limit + 1);
+    }
+
+    /// Cuts the fetched rows down to one page and works out the next cursor.
+    pub fn page<T: Paginated>(&self, mut items: Vec<T>, uri: &Uri) -> Page<T> {
+        let next_cursor = if items.len() as i64 > self.limit {
+            items.truncate(self.limit as usize);
+            items.last().map(|last| {
+                let value = match self.sort {
+                    SortField::Name => last.name().to_string(),
+                    SortField::CreatedAt => format_timestamp(last.created_at()),
+                    SortField::UpdatedAt => format_timestamp(last.updated_at()),
+                };
+                Cursor { sort: self.sort, desc: self.desc, value, id: last.cursor_id() }.encode()
+            })
+        } else {
+            None
+        };
+
+        let next_link = next_cursor.as_deref().map(|cursor| next_link(uri, cursor));
+        Page { items, next_cursor, next_link }
+    }
+}
+
+/// One page of a list. Serializes as a bare JSON array so existing
+/// clients keep working; pagination metadata travels in headers.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    next_link: Option<String>,
+}
+
+impl<T: Serialize> IntoResponse for Page<T> {
+    fn into_response(self) -> Response {
+        let mut response = Json(self.items).into_response();
+        let headers = response.headers_mut();
+        if let Some(value) = self.next_cursor.and_then(|cursor| HeaderValue::from_str(&cursor).ok()) {
+            headers.insert(NEXT_CURSOR_HEADER, value);
+        }
+        if let Some(value) = self
+            .next_link
+            .and_then(|link| HeaderValue::from_str(&format!("<{}>; rel=\"next\"", link)).ok())
+        {
+            headers.insert(header::LINK, value);
+        }
+        response
+    }
+}
+
+// Same path and query with the cursor swapped for the next one
+fn next_link(uri: &Uri, cursor: &str) -> String {
+    let mut params: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("cursor="))
+        .collect();
+    let cursor = format!("cursor={}", cursor);
+    params.push(&cursor);
+    format!("{}?{}", uri.path(), params.join("&"))
+}
+
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    value
+        .parse::<DateTime<Utc>>()
+        .map(|timestamp| timestamp.naive_utc())
+        .or_else(|_| value.parse::<NaiveDateTime>())
+        .ok()
+}
+
+fn format_timestamp(timestamp: NaiveDateTime) -> String {
+    timestamp.and_utc().to_rfc3339()
+}
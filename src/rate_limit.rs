@@ -0,0 +1,240 @@
+//! Token-bucket rate limiting.
+//!
+//! Each group of routes (sync, auth and the CRUD API) has its own budget,
+//! tracked per authenticated user or, for anonymous callers, per IP
+//! address. Callers that run out get a `429` with `Retry-After`. Buckets
+//! live in a [`RateLimitStore`]; [`MemoryStore`] keeps them in-process,
+//! which is enough for a single instance but lets a client get a full
+//! budget from every replica.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, State},
+    http::{header, HeaderMap, HeaderName, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::auth::{AuthKeys, AuthUser};
+use crate::config::{Budget, RateLimitConfig};
+use crate::error::ApiError;
+use crate::state::AppState;
+
+const FORWARDED_FOR_HEADER: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+// How often the in-process store drops buckets that have refilled
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The groups of routes that are budgeted separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Sync,
+    Auth,
+    Crud,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sync => "sync",
+            Self::Auth => "auth",
+            Self::Crud => "crud",
+        }
+    }
+}
+
+/// Outcome of asking a store for a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    /// The bucket is empty and holds a token again after this long.
+    Deny { retry_after: Duration },
+}
+
+/// Where buckets are kept. Implement this over a shared store such as
+/// Redis to enforce one budget across several instances.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from the bucket named `key`, creating it full if it
+    /// does not exist yet.
+    async fn acquire(&self, key: &str, budget: Budget) -> Decision;
+}
+
+/// Buckets held in this process's memory.
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    buckets: HashMap<String, Bucket>,
+    last_sweep: Option<Instant>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    // From this point on the bucket is full and can be forgotten
+    full_at: Instant,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn acquire(&self, key: &str, budget: Budget) -> Decision {
+        let now = Instant::now();
+        let capacity = f64::from(budget.burst);
+        let per_second = f64::from(budget.per_minute) / 60.0;
+
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if state.last_sweep.is_none_or(|at| now.duration_since(at) >= SWEEP_INTERVAL) {
+            state.buckets.retain(|_, bucket| bucket.full_at > now);
+            state.last_sweep = Some(now);
+        }
+
+        let bucket = state.buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+            full_at: now,
+        });
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated_at = now;
+
+        let decision = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Decision::Allow
+        } else {
+            Decision::Deny {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / per_second),
+            }
+        };
+        bucket.full_at = now + Duration::from_secs_f64((capacity - bucket.tokens) / per_second);
+        decision
+    }
+}
+
+/// The configured budgets and the store tracking them. Clones share the
+/// store.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    store: Arc<dyn RateLimitStore>,
+}
+
+// Middleware state for one group of routes
+#[derive(Clone)]
+struct Limit {
+    limiter: RateLimiter,
+    scope: Scope,
+    budget: Budget,
+    // Lets `enforce` tell who is calling
+    auth: AuthKeys,
+    pool: PgPool,
+}
+
+impl FromRef<Limit> for AuthKeys {
+    fn from_ref(limit: &Limit) -> Self {
+        limit.auth.clone()
+    }
+}
+
+impl FromRef<Limit> for PgPool {
+    fn from_ref(limit: &Limit) -> Self {
+        limit.pool.clone()
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig, store: Arc<dyn RateLimitStore>) -> Self {
+        Self { config: Arc::new(config), store }
+    }
+
+    // Who the request is charged to: the user when the token is valid,
+    // otherwise the client's address
+    fn caller(&self, user: Option<AuthUser>, peer: Option<SocketAddr>, headers: &HeaderMap) -> String {
+        if let Some(user) = user {
+            return format!("user:{}", user.user_id);
+        }
+
+        match self.forwarded_for(headers).or(peer.map(|peer| peer.ip())) {
+            Some(ip) => format!("ip:{}", ip),
+            None => "ip:unknown".to_string(),
+        }
+    }
+
+    // The client address as reported by the outermost trusted proxy. Each
+    // proxy appends the address it received the request from, so anything
+    // left of the last `trusted_proxies` entries may have been made up by
+    // the client and is skipped.
+    fn forwarded_for(&self, headers: &HeaderMap) -> Option<IpAddr> {
+        let hops = self.config.trusted_proxies;
+        if hops == 0 {
+            return None;
+        }
+        let mut addresses = Vec::new();
+        for value in headers.get_all(FORWARDED_FOR_HEADER) {
+            addresses.extend(value.to_str().ok()?.split(','));
+        }
+        let index = addresses.len().checked_sub(hops)?;
+        addresses[index].trim().parse().ok()
+    }
+}
+
+/// Limits every route in `router` with the budget for `scope`. Does
+/// nothing when rate limiting is switched off.
+pub fn limit(state: &AppState, scope: Scope, router: Router) -> Router {
+    let limiter = &state.rate_limiter;
+    if !limiter.config.enabled {
+        return router;
+    }
+
+    let budget = match scope {
+        Scope::Sync => limiter.config.sync,
+        Scope::Auth => limiter.config.auth,
+        Scope::Crud => limiter.config.crud,
+    };
+    let limit = Limit {
+        limiter: limiter.clone(),
+        scope,
+        budget,
+        auth: state.auth.clone(),
+        pool: state.pool.clone(),
+    };
+    router.route_layer(middleware::from_fn_with_state(limit, enforce))
+}
+
+async fn enforce<B>(
+    State(limit): State<Limit>,
+    user: Option<AuthUser>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let caller = limit
+        .limiter
+        .caller(user, peer.map(|ConnectInfo(peer)| peer), request.headers());
+    let key = format!("{}:{}", limit.scope.as_str(), caller);
+
+    match limit.limiter.store.acquire(&key, limit.budget).await {
+        Decision::Allow => next.run(request).await,
+        Decision::Deny { retry_after } => {
+            // Whole seconds, rounded up so retrying on time succeeds
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            tracing::warn!(scope = limit.scope.as_str(), caller, "Rate limit exceeded");
+            let error = ApiError::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited", "Too many requests")
+                .with_details(json!({ "scope": limit.scope.as_str(), "retry_after_seconds": seconds }));
+            ([(header::RETRY_AFTER, seconds.to_string())], error).into_response()
+        }
+    }
+}
@@ -1,92 +1,242 @@
-use axum::{Json, extract::Path, extract::State};
-use sqlx::PgPool;
+use axum::{Json, extract::{OriginalUri, State}};
+use sqlx::{PgPool, QueryBuilder};
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::extract::{Path, Query};
+use crate::pagination::{Columns, ListParams, Page, Paginated};
+use crate::tombstones;
+use crate::etag::{self, IfMatch, Tagged};
+use crate::sync::ItemKind;
+use crate::validation::{nullable, FieldErrors, ValidJson, Validate};
+use chrono::Utc;
 use crate::models::archive::Archive;
+use crate::models::tome::Tome;
+use crate::models::tree::{ArchiveTree, EntrySummary, TomeTree};
+use std::collections::HashMap;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
 pub struct CreateArchivePayload {
     pub name: String,
-    pub description: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateArchivePayload {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Body for `PATCH`: only the fields present are changed.
+#[derive(Deserialize)]
+pub struct PatchArchivePayload {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub description: Option<Option<String>>,
+}
+
+impl Validate for CreateArchivePayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+    }
+}
+
+impl Validate for UpdateArchivePayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+    }
+}
+
+impl Validate for PatchArchivePayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.name("name", name);
+        }
+    }
 }
 
 pub async fn list_archives(
+    auth: AuthUser,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<ListParams>,
     State(pool): State<PgPool>
-) -> Result<Json<Vec<Archive>>, axum::http::StatusCode> {
-    // This function retrieves all archives from the database
-    // and returns them as a JSON response.
-    let archives = sqlx::query_as::<_, Archive>("SELECT * FROM archives")
-        .fetch_all(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    Ok(Json(archives))
+) -> Result<Page<Archive>, ApiError> {
+    // This function retrieves one page of the caller's archives, sorted
+    // and filtered as requested, and returns it as a JSON response.
+    let list = params.resolve()?;
+
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM archives WHERE user_id = ", Archive::COLUMNS));
+    query.push_bind(auth.user_id).push(" AND deleted_at IS NULL");
+    list.apply(&mut query, &Columns::DEFAULT);
+
+    let archives = query.build_query_as::<Archive>().fetch_all(&pool).await?;
+
+    Ok(list.page(archives, &uri))
 }
 
 pub async fn create_archive(
+    auth: AuthUser,
     State(pool): State<PgPool>,
-    Json(payload): Json<CreateArchivePayload>
-) -> Result<Json<Archive>, axum::http::StatusCode> {
+    ValidJson(payload): ValidJson<CreateArchivePayload>
+) -> Result<Tagged<Archive>, ApiError> {
     // This function creates a new archive in the database
     // using the provided JSON payload and returns the created archive.
-    let new_archive = sqlx::query_as::<_, Archive>(
-        "INSERT INTO archives (id, user_id, name, description, created_at, updated_at) VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP) RETURNING *"
+    let new_archive = sqlx::query_as!(
+        Archive,
+        "INSERT INTO archives (id, user_id, name, description, created_at, updated_at) VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP) RETURNING id, user_id, name, description, created_at, updated_at, revision",
+        format!("archive-{}", uuid::Uuid::new_v4()),
+        auth.user_id,
+        payload.name,
+        payload.description
     )
-    .bind(format!("archive-{}", uuid::Uuid::new_v4()))
-    .bind("temp-user-123") // Use temporary user ID
-    .bind(&payload.name)
-    .bind(&payload.description)
     .fetch_one(&pool)
-    .await
-    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await?;
     
-    Ok(Json(new_archive))
+    Ok(Tagged(new_archive))
 }
 
 pub async fn update_archive(
+    auth: AuthUser,
     Path(id): Path<String>,
+    if_match: IfMatch,
     State(pool): State<PgPool>,
-    Json(payload): Json<Archive>
-) -> Result<Json<Archive>, axum::http::StatusCode> {
+    ValidJson(payload): ValidJson<UpdateArchivePayload>
+) -> Result<Tagged<Archive>, ApiError> {
     // This function updates an existing archive in the database
     // using the provided ID and JSON payload, returning the updated archive.
-    let updated_archive = sqlx::query_as::<_, Archive>(
-        "UPDATE archives SET name = $1, description = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3 RETURNING *"
+    // A stale If-Match is rejected with 412.
+    let updated_archive = sqlx::query_as!(
+        Archive,
+        "UPDATE archives SET name = $1, description = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3 AND user_id = $4 AND deleted_at IS NULL AND ($5::bigint[] IS NULL OR revision = ANY($5)) RETURNING id, user_id, name, description, created_at, updated_at, revision",
+        payload.name,
+        payload.description,
+        id,
+        auth.user_id,
+        if_match.revisions()
     )
-    .bind(&payload.name)
-    .bind(&payload.description)
-    .bind(id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
-    
-    Ok(Json(updated_archive))
+    .fetch_optional(&pool)
+    .await?;
+
+    match updated_archive {
+        Some(archive) => Ok(Tagged(archive)),
+        None => Err(etag::write_failed(&pool, ItemKind::Archive, &id, auth.user_id).await),
+    }
+}
+
+pub async fn patch_archive(
+    auth: AuthUser,
+    Path(id): Path<String>,
+    if_match: IfMatch,
+    State(pool): State<PgPool>,
+    ValidJson(payload): ValidJson<PatchArchivePayload>
+) -> Result<Tagged<Archive>, ApiError> {
+    // This function changes only the fields present in the JSON payload
+    // and returns the updated archive. A stale If-Match is rejected with 412.
+    let updated_archive = sqlx::query_as!(
+        Archive,
+        "UPDATE archives SET name = COALESCE($1, name), description = CASE WHEN $2 THEN $3 ELSE description END, updated_at = CURRENT_TIMESTAMP WHERE id = $4 AND user_id = $5 AND deleted_at IS NULL AND ($6::bigint[] IS NULL OR revision = ANY($6)) RETURNING id, user_id, name, description, created_at, updated_at, revision",
+        payload.name,
+        payload.description.is_some(),
+        payload.description.flatten(),
+        id,
+        auth.user_id,
+        if_match.revisions()
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    match updated_archive {
+        Some(archive) => Ok(Tagged(archive)),
+        None => Err(etag::write_failed(&pool, ItemKind::Archive, &id, auth.user_id).await),
+    }
 }
 
 pub async fn get_archive(
+    auth: AuthUser,
     Path(id): Path<String>,
     State(pool): State<PgPool>
-) -> Result<Json<Archive>, axum::http::StatusCode> {
+) -> Result<Tagged<Archive>, ApiError> {
     // This function retrieves a specific archive by its ID from the database
     // and returns it as a JSON response.
-    let archive = sqlx::query_as::<_, Archive>("SELECT * FROM archives WHERE id = $1")
-        .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
+    let archive = sqlx::query_as!(
+        Archive,
+        "SELECT id, user_id, name, description, created_at, updated_at, revision FROM archives WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("archive"))?;
     
-    Ok(Json(archive))
+    Ok(Tagged(archive))
+}
+
+pub async fn get_archive_tree(
+    auth: AuthUser,
+    Path(id): Path<String>,
+    State(pool): State<PgPool>
+) -> Result<Json<ArchiveTree>, ApiError> {
+    // This function returns an archive together with its tomes and a
+    // summary of every entry in them, in sidebar order, in one response.
+    let archive = sqlx::query_as!(
+        Archive,
+        "SELECT id, user_id, name, description, created_at, updated_at, revision FROM archives WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("archive"))?;
+
+    let tomes = sqlx::query_as!(
+        Tome,
+        "SELECT id, archive_id, user_id, name, description, created_at, updated_at, position, revision FROM tomes WHERE archive_id = $1 AND user_id = $2 AND deleted_at IS NULL ORDER BY position, created_at, id",
+        id,
+        auth.user_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let entries = sqlx::query_as!(
+        EntrySummary,
+        "SELECT e.id, e.tome_id, e.title, e.position, e.created_at, e.updated_at FROM entries e JOIN tomes t ON t.id = e.tome_id WHERE t.archive_id = $1 AND e.user_id = $2 AND e.deleted_at IS NULL ORDER BY e.position, e.created_at, e.id",
+        id,
+        auth.user_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut entries_by_tome: HashMap<String, Vec<EntrySummary>> = HashMap::new();
+    for entry in entries {
+        entries_by_tome.entry(entry.tome_id.clone()).or_default().push(entry);
+    }
+
+    let tomes = tomes
+        .into_iter()
+        .map(|tome| TomeTree {
+            entries: entries_by_tome.remove(&tome.id).unwrap_or_default(),
+            tome,
+        })
+        .collect();
+
+    Ok(Json(ArchiveTree { archive, tomes }))
 }
 
 pub async fn delete_archive(
+    auth: AuthUser,
     Path(id): Path<String>,
     State(pool): State<PgPool>
-) -> Result<(), axum::http::StatusCode> {
-    // This function deletes an archive by its ID from the database.
-    sqlx::query("DELETE FROM archives WHERE id = $1")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
+) -> Result<(), ApiError> {
+    // This function deletes an archive by its ID, leaving a tombstone for
+    // sync clients and cascading to everything beneath it.
+    let mut tx = pool.begin().await?;
+    let deleted = tombstones::delete_archive(&mut tx, auth.user_id, &id, Utc::now())
+        .await?;
+
+    if !deleted {
+        return Err(ApiError::not_found("archive"));
+    }
+    tx.commit().await?;
     
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,142 @@
+use axum::{Json, extract::State};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+use crate::auth::{AuthKeys, hash_password, verify_password};
+use crate::config::Features;
+use crate::error::ApiError;
+use crate::validation::{FieldErrors, ValidJson, Validate};
+
+#[derive(Deserialize)]
+pub struct RegisterPayload {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+}
+
+impl Validate for RegisterPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.username("username", self.username.trim());
+        errors.email("email", self.email.trim());
+        errors.password("password", &self.password);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LoginPayload {
+    /// Either the username or the email address of the account.
+    pub username: String,
+    pub password: String,
+}
+
+impl Validate for LoginPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        // Only presence: accounts may predate the current password rules
+        if self.username.trim().is_empty() {
+            errors.add("username", "must not be empty");
+        }
+        if self.password.is_empty() {
+            errors.add("password", "must not be empty");
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AuthResponse {
+    pub token: String,
+    pub token_type: &'static str,
+    pub expires_at: DateTime<Utc>,
+    pub user_id: Uuid,
+    pub username: String,
+}
+
+fn auth_response(keys: &AuthKeys, user_id: Uuid, username: String) -> Result<Json<AuthResponse>, ApiError> {
+    let (token, expires_at) = keys
+        .issue_token(user_id)
+        .map_err(ApiError::internal)?;
+
+    Ok(Json(AuthResponse {
+        token,
+        token_type: "Bearer",
+        expires_at,
+        user_id,
+        username,
+    }))
+}
+
+pub async fn register(
+    State(pool): State<PgPool>,
+    State(keys): State<AuthKeys>,
+    State(features): State<Features>,
+    ValidJson(payload): ValidJson<RegisterPayload>
+) -> Result<Json<AuthResponse>, ApiError> {
+    // This function creates a new account with an argon2 password hash
+    // and returns a session token for it, unless registration is disabled.
+    if !features.registration {
+        return Err(ApiError::forbidden("Registration is disabled"));
+    }
+    let password_hash = hash_password(payload.password).await?;
+
+    let user_id = Uuid::new_v4();
+    sqlx::query!(
+        "INSERT INTO users (id, username, email, password_hash, created_at, is_active) VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP, true)",
+        user_id,
+        payload.username.trim(),
+        payload.email.trim(),
+        password_hash
+    )
+    .execute(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            ApiError::conflict("Username or email is already taken")
+        }
+        e => ApiError::from(e),
+    })?;
+
+    auth_response(&keys, user_id, payload.username.trim().to_string())
+}
+
+pub async fn login(
+    State(pool): State<PgPool>,
+    State(keys): State<AuthKeys>,
+    ValidJson(payload): ValidJson<LoginPayload>
+) -> Result<Json<AuthResponse>, ApiError> {
+    // This function checks the supplied credentials and, if they match an
+    // active account, returns a fresh session token.
+    // Usernames cannot contain `@`, so the identifier names at most one
+    // account either way
+    let identifier = payload.username.trim();
+    let row = if identifier.contains('@') {
+        sqlx::query!(
+            "SELECT id, username, password_hash, is_active FROM users WHERE email = $1",
+            identifier
+        )
+        .fetch_optional(&pool)
+        .await?
+        .map(|row| (row.id, row.username, row.password_hash, row.is_active))
+    } else {
+        sqlx::query!(
+            "SELECT id, username, password_hash, is_active FROM users WHERE username = $1",
+            identifier
+        )
+        .fetch_optional(&pool)
+        .await?
+        .map(|row| (row.id, row.username, row.password_hash, row.is_active))
+    };
+    let (user_id, username, password_hash, is_active) = row.ok_or_else(ApiError::unauthorized)?;
+
+    if !verify_password(payload.password, password_hash).await? || !is_active {
+        return Err(ApiError::unauthorized());
+    }
+
+    sqlx::query!(
+        "UPDATE users SET last_login = CURRENT_TIMESTAMP WHERE id = $1",
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    auth_response(&keys, user_id, username)
+}
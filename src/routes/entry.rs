@@ -1,87 +1,329 @@
-use axum::{Json, extract::Path, extract::State};
-use sqlx::PgPool;
+use axum::{extract::{OriginalUri, State}, http::Uri};
+use serde::Deserialize;
+use sqlx::{PgPool, QueryBuilder};
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::extract::{Path, Query};
+use crate::pagination::{Columns, ListParams, Page, Paginated};
 use crate::models::entry::Entry;
+use crate::etag::{self, IfMatch, Tagged};
+use crate::sync::ItemKind;
+use crate::ordering;
+use crate::tombstones;
+use chrono::Utc;
+use crate::validation::{FieldErrors, ValidJson, Validate};
 
-pub async fn list_entries( 
+#[derive(Deserialize)]
+pub struct EntryFilter {
+    pub tome_id: Option<String>,
+}
+
+/// Body for creating an entry under `/tomes/:id/entries`, where the parent
+/// comes from the path.
+#[derive(Deserialize)]
+pub struct CreateChildEntryPayload {
+    /// Client-chosen id, as used by sync clients; generated when absent.
+    pub id: Option<String>,
+    pub title: String,
+    pub content: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateEntryPayload {
+    /// Client-chosen id, as used by sync clients; generated when absent.
+    pub id: Option<String>,
+    pub tome_id: String,
+    pub title: String,
+    pub content: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateEntryPayload {
+    pub title: String,
+    pub content: String,
+}
+
+/// Body for `PATCH`: only the fields present are changed.
+#[derive(Deserialize)]
+pub struct PatchEntryPayload {
+    pub title: Option<String>,
+    pub content: Option<String>,
+}
+
+/// Body for `POST /entries/:id/move`.
+#[derive(Deserialize)]
+pub struct MoveEntryPayload {
+    /// New parent tome; stays in the current one when absent.
+    pub tome_id: Option<String>,
+    /// Zero-based place among the tome's other entries; the end when
+    /// absent or past the end.
+    pub position: Option<i32>,
+}
+
+impl Validate for CreateChildEntryPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.optional_id("id", self.id.as_deref());
+        errors.name("title", &self.title);
+    }
+}
+
+impl Validate for CreateEntryPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.optional_id("id", self.id.as_deref());
+        errors.id("tome_id", &self.tome_id);
+        errors.name("title", &self.title);
+    }
+}
+
+impl Validate for UpdateEntryPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("title", &self.title);
+    }
+}
+
+impl Validate for PatchEntryPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if let Some(title) = &self.title {
+            errors.name("title", title);
+        }
+    }
+}
+
+impl Validate for MoveEntryPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.optional_id("tome_id", self.tome_id.as_deref());
+        errors.position("position", self.position);
+    }
+}
+
+async fn fetch_entries(
+    pool: &PgPool,
+    auth: &AuthUser,
+    tome_id: Option<String>,
+    params: ListParams,
+    uri: &Uri,
+) -> Result<Page<Entry>, ApiError> {
+    let list = params.resolve()?;
+
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM entries WHERE user_id = ", Entry::COLUMNS));
+    query.push_bind(auth.user_id).push(" AND deleted_at IS NULL");
+    if let Some(tome_id) = tome_id {
+        query.push(" AND tome_id = ").push_bind(tome_id);
+    }
+    list.apply(&mut query, &Columns { name: "title", id: "id" });
+
+    let entries = query.build_query_as::<Entry>().fetch_all(pool).await?;
+
+    Ok(list.page(entries, uri))
+}
+
+pub async fn list_entries(
+    auth: AuthUser,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<ListParams>,
+    Query(filter): Query<EntryFilter>,
     State(pool): State<PgPool>
-) -> Result<Json<Vec<Entry>>, axum::http::StatusCode> {
-    // This function retrieves all entries from the database
-    // and returns them as a JSON response.
-    let entries = sqlx::query_as::<_, Entry>("SELECT * FROM entries")
-        .fetch_all(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    Ok(Json(entries))
+) -> Result<Page<Entry>, ApiError> {
+    // This function retrieves one page of the caller's entries, optionally
+    // limited to one tome, and returns it as a JSON response.
+    fetch_entries(&pool, &auth, filter.tome_id, params, &uri).await
+}
+
+pub async fn list_tome_entries(
+    auth: AuthUser,
+    Path(tome_id): Path<String>,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<ListParams>,
+    State(pool): State<PgPool>
+) -> Result<Page<Entry>, ApiError> {
+    // This function retrieves one page of the entries in one of the
+    // caller's tomes and returns it as a JSON response.
+    sqlx::query!(
+        "SELECT id FROM tomes WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        tome_id,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("tome"))?;
+
+    fetch_entries(&pool, &auth, Some(tome_id), params, &uri).await
 }
 
 pub async fn create_entry(
+    auth: AuthUser,
     State(pool): State<PgPool>,
-    Json(payload): Json<Entry>
-) -> Result<Json<Entry>, axum::http::StatusCode> {
+    ValidJson(payload): ValidJson<CreateEntryPayload>
+) -> Result<Tagged<Entry>, ApiError> {
     // This function creates a new entry in the database
     // using the provided JSON payload and returns the created entry.
-    let new_entry = sqlx::query_as::<_, Entry>(
-        "INSERT INTO entries (id, tome_id, user_id, title, content, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP) RETURNING *"
+    // The parent tome must belong to the caller.
+    let new_entry = sqlx::query_as!(
+        Entry,
+        "INSERT INTO entries (id, tome_id, user_id, title, content, position, created_at, updated_at) SELECT $1, id, $3, $4, $5, COALESCE((SELECT MAX(position) + (MAX(position) < $6)::int FROM entries WHERE tome_id = $2 AND deleted_at IS NULL), 0), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP FROM tomes WHERE id = $2 AND user_id = $3 AND deleted_at IS NULL RETURNING id, tome_id, user_id, title, content, created_at, updated_at, position, revision",
+        payload.id.unwrap_or_else(|| format!("entry-{}", uuid::Uuid::new_v4())),
+        payload.tome_id,
+        auth.user_id,
+        payload.title,
+        payload.content,
+        ordering::MAX_POSITION
     )
-    .bind(&payload.id)
-    .bind(&payload.tome_id)
-    .bind(&payload.user_id)
-    .bind(&payload.title)
-    .bind(&payload.content)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("tome"))?;
     
-    Ok(Json(new_entry))
+    Ok(Tagged(new_entry))
+}
+
+pub async fn create_tome_entry(
+    auth: AuthUser,
+    Path(tome_id): Path<String>,
+    State(pool): State<PgPool>,
+    ValidJson(payload): ValidJson<CreateChildEntryPayload>
+) -> Result<Tagged<Entry>, ApiError> {
+    // This function creates a new entry inside one of the caller's tomes
+    // and returns the created entry.
+    let new_entry = sqlx::query_as!(
+        Entry,
+        "INSERT INTO entries (id, tome_id, user_id, title, content, position, created_at, updated_at) SELECT $1, id, $3, $4, $5, COALESCE((SELECT MAX(position) + (MAX(position) < $6)::int FROM entries WHERE tome_id = $2 AND deleted_at IS NULL), 0), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP FROM tomes WHERE id = $2 AND user_id = $3 AND deleted_at IS NULL RETURNING id, tome_id, user_id, title, content, created_at, updated_at, position, revision",
+        payload.id.unwrap_or_else(|| format!("entry-{}", uuid::Uuid::new_v4())),
+        tome_id,
+        auth.user_id,
+        payload.title,
+        payload.content,
+        ordering::MAX_POSITION
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("tome"))?;
+
+    Ok(Tagged(new_entry))
 }
 
 pub async fn update_entry(
+    auth: AuthUser,
     Path(id): Path<String>,
+    if_match: IfMatch,
     State(pool): State<PgPool>,
-    Json(payload): Json<Entry>
-) -> Result<Json<Entry>, axum::http::StatusCode> {
+    ValidJson(payload): ValidJson<UpdateEntryPayload>
+) -> Result<Tagged<Entry>, ApiError> {
     // This function updates an existing entry in the database
     // using the provided ID and JSON payload, returning the updated entry.
-    let updated_entry = sqlx::query_as::<_, Entry>(
-        "UPDATE entries SET title = $1, content = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3 RETURNING *"
+    // A stale If-Match is rejected with 412.
+    let updated_entry = sqlx::query_as!(
+        Entry,
+        "UPDATE entries SET title = $1, content = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3 AND user_id = $4 AND deleted_at IS NULL AND ($5::bigint[] IS NULL OR revision = ANY($5)) RETURNING id, tome_id, user_id, title, content, created_at, updated_at, position, revision",
+        payload.title,
+        payload.content,
+        id,
+        auth.user_id,
+        if_match.revisions()
     )
-    .bind(&payload.title)
-    .bind(&payload.content)
-    .bind(id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
-    
-    Ok(Json(updated_entry))
+    .fetch_optional(&pool)
+    .await?;
+
+    match updated_entry {
+        Some(entry) => Ok(Tagged(entry)),
+        None => Err(etag::write_failed(&pool, ItemKind::Entry, &id, auth.user_id).await),
+    }
+}
+
+pub async fn patch_entry(
+    auth: AuthUser,
+    Path(id): Path<String>,
+    if_match: IfMatch,
+    State(pool): State<PgPool>,
+    ValidJson(payload): ValidJson<PatchEntryPayload>
+) -> Result<Tagged<Entry>, ApiError> {
+    // This function changes only the fields present in the JSON payload
+    // and returns the updated entry. A stale If-Match is rejected with 412.
+    let updated_entry = sqlx::query_as!(
+        Entry,
+        "UPDATE entries SET title = COALESCE($1, title), content = COALESCE($2, content), updated_at = CURRENT_TIMESTAMP WHERE id = $3 AND user_id = $4 AND deleted_at IS NULL AND ($5::bigint[] IS NULL OR revision = ANY($5)) RETURNING id, tome_id, user_id, title, content, created_at, updated_at, position, revision",
+        payload.title,
+        payload.content,
+        id,
+        auth.user_id,
+        if_match.revisions()
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    match updated_entry {
+        Some(entry) => Ok(Tagged(entry)),
+        None => Err(etag::write_failed(&pool, ItemKind::Entry, &id, auth.user_id).await),
+    }
+}
+
+pub async fn move_entry(
+    auth: AuthUser,
+    Path(id): Path<String>,
+    if_match: IfMatch,
+    State(pool): State<PgPool>,
+    ValidJson(payload): ValidJson<MoveEntryPayload>
+) -> Result<Tagged<Entry>, ApiError> {
+    // This function moves a entry to another of the caller's tomes
+    // and/or to a new place among its siblings, and returns the moved entry.
+    // A stale If-Match is rejected with 412.
+    let mut tx = pool.begin().await?;
+    ordering::move_item(
+        &mut tx,
+        ordering::Siblings::Entries,
+        auth.user_id,
+        &id,
+        payload.tome_id.as_deref(),
+        payload.position,
+        if_match.revisions(),
+    )
+    .await?;
+
+    let moved_entry = sqlx::query_as!(
+        Entry,
+        "SELECT id, tome_id, user_id, title, content, created_at, updated_at, position, revision FROM entries WHERE id = $1 AND user_id = $2",
+        id,
+        auth.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Tagged(moved_entry))
 }
 
 pub async fn get_entry(
+    auth: AuthUser,
     Path(id): Path<String>,
     State(pool): State<PgPool>
-) -> Result<Json<Entry>, axum::http::StatusCode> {
+) -> Result<Tagged<Entry>, ApiError> {
     // This function retrieves a specific entry by its ID from the database
     // and returns it as a JSON response.
-    let entry = sqlx::query_as::<_, Entry>("SELECT * FROM entries WHERE id = $1")
-        .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
+    let entry = sqlx::query_as!(
+        Entry,
+        "SELECT id, tome_id, user_id, title, content, created_at, updated_at, position, revision FROM entries WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("entry"))?;
     
-    Ok(Json(entry))
+    Ok(Tagged(entry))
 }
 
 pub async fn delete_entry(
+    auth: AuthUser,
     Path(id): Path<String>,
     State(pool): State<PgPool>
-) -> Result<Json<Entry>, axum::http::StatusCode> {
-    // This function deletes a specific entry by its ID from the database
-    // and returns the deleted entry as a JSON response.
-    let deleted_entry = sqlx::query_as::<_, Entry>("DELETE FROM entries WHERE id = $1 RETURNING *")
-        .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
-    
-    Ok(Json(deleted_entry))
-}
\ No newline at end of file
+) -> Result<(), ApiError> {
+    // This function deletes a specific entry by its ID, leaving a tombstone
+    // for sync clients.
+    let mut conn = pool.acquire().await?;
+    let deleted = tombstones::delete_entry(&mut conn, auth.user_id, &id, Utc::now())
+        .await?;
+
+    if !deleted {
+        return Err(ApiError::not_found("entry"));
+    }
+
+    Ok(())
+}
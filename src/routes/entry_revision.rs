@@ -0,0 +1,156 @@
+use axum::{Json, extract::State};
+use serde::Deserialize;
+use similar::TextDiff;
+use sqlx::PgPool;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::extract::{Path, Query};
+use crate::etag::Tagged;
+use crate::models::entry::Entry;
+use crate::models::entry_revision::{EntryRevision, EntryRevisionDiff, EntryRevisionSummary};
+
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    /// Revision to compare against; defaults to the one just before.
+    pub from: Option<i64>,
+}
+
+async fn ensure_entry(pool: &PgPool, auth: &AuthUser, id: &str) -> Result<(), ApiError> {
+    sqlx::query!(
+        "SELECT id FROM entries WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        auth.user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|_| ())
+    .ok_or(ApiError::not_found("entry"))
+}
+
+async fn fetch_revision(
+    pool: &PgPool,
+    auth: &AuthUser,
+    id: &str,
+    revision: i64,
+) -> Result<Option<EntryRevision>, ApiError> {
+    sqlx::query_as!(
+        EntryRevision,
+        "SELECT entry_id, revision, title, content, created_at FROM entry_revisions WHERE entry_id = $1 AND user_id = $2 AND revision = $3",
+        id,
+        auth.user_id,
+        revision
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(ApiError::from)
+}
+
+pub async fn list_revisions(
+    auth: AuthUser,
+    Path(id): Path<String>,
+    State(pool): State<PgPool>
+) -> Result<Json<Vec<EntryRevisionSummary>>, ApiError> {
+    // This function lists the saved versions of an entry, newest first,
+    // without their content.
+    ensure_entry(&pool, &auth, &id).await?;
+
+    let revisions = sqlx::query_as!(
+        EntryRevisionSummary,
+        "SELECT revision, title, LENGTH(content) AS \"content_length!\", created_at FROM entry_revisions WHERE entry_id = $1 AND user_id = $2 ORDER BY revision DESC",
+        id,
+        auth.user_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(revisions))
+}
+
+pub async fn get_revision(
+    auth: AuthUser,
+    Path((id, revision)): Path<(String, i64)>,
+    State(pool): State<PgPool>
+) -> Result<Json<EntryRevision>, ApiError> {
+    // This function returns one saved version of an entry in full.
+    ensure_entry(&pool, &auth, &id).await?;
+
+    let revision = fetch_revision(&pool, &auth, &id, revision)
+        .await?
+        .ok_or(ApiError::not_found("revision"))?;
+
+    Ok(Json(revision))
+}
+
+pub async fn diff_revision(
+    auth: AuthUser,
+    Path((id, revision)): Path<(String, i64)>,
+    Query(params): Query<DiffQuery>,
+    State(pool): State<PgPool>
+) -> Result<Json<EntryRevisionDiff>, ApiError> {
+    // This function compares a saved version with an earlier one (or any
+    // other version given as `from`) and returns a unified diff.
+    ensure_entry(&pool, &auth, &id).await?;
+
+    let to = fetch_revision(&pool, &auth, &id, revision)
+        .await?
+        .ok_or(ApiError::not_found("revision"))?;
+
+    let from = match params.from {
+        Some(from) => Some(
+            fetch_revision(&pool, &auth, &id, from)
+                .await?
+                .ok_or(ApiError::not_found("revision"))?,
+        ),
+        None => sqlx::query_as!(
+            EntryRevision,
+            "SELECT entry_id, revision, title, content, created_at FROM entry_revisions WHERE entry_id = $1 AND user_id = $2 AND revision < $3 ORDER BY revision DESC LIMIT 1",
+            id,
+            auth.user_id,
+            revision
+        )
+        .fetch_optional(&pool)
+        .await?,
+    };
+
+    // The first revision is compared with an empty entry
+    let (from_revision, from_title, from_content) = match from {
+        Some(from) => (from.revision, from.title, from.content),
+        None => (0, String::new(), String::new()),
+    };
+
+    let diff = TextDiff::from_lines(&from_content, &to.content)
+        .unified_diff()
+        .header(&format!("{}@{}", id, from_revision), &format!("{}@{}", id, to.revision))
+        .to_string();
+
+    Ok(Json(EntryRevisionDiff {
+        entry_id: id,
+        from: from_revision,
+        to: to.revision,
+        from_title,
+        to_title: to.title,
+        diff,
+    }))
+}
+
+pub async fn restore_revision(
+    auth: AuthUser,
+    Path((id, revision)): Path<(String, i64)>,
+    State(pool): State<PgPool>
+) -> Result<Tagged<Entry>, ApiError> {
+    // This function puts an earlier version back as the entry's current
+    // content. The restore itself becomes a new revision, so it can be
+    // undone and reaches sync clients like any other edit.
+    let restored_entry = sqlx::query_as!(
+        Entry,
+        "UPDATE entries SET title = r.title, content = r.content, updated_at = CURRENT_TIMESTAMP FROM entry_revisions r WHERE entries.id = $1 AND entries.user_id = $2 AND entries.deleted_at IS NULL AND r.entry_id = entries.id AND r.user_id = entries.user_id AND r.revision = $3 RETURNING entries.id, entries.tome_id, entries.user_id, entries.title, entries.content, entries.created_at, entries.updated_at, entries.position, entries.revision",
+        id,
+        auth.user_id,
+        revision
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("revision"))?;
+
+    Ok(Tagged(restored_entry))
+}
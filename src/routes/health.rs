@@ -0,0 +1,106 @@
+//! Probes for orchestrators and load balancers, served outside the
+//! versioned API.
+
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::Serialize;
+use sqlx::{migrate::Migrate, Connection, PgPool};
+
+use crate::state::AppState;
+use crate::MIGRATOR;
+
+// A readiness probe that hangs is as bad as one that fails
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+pub struct HealthStatus {
+    pub status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessChecks {
+    pub database: String,
+    pub migrations: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessStatus {
+    pub status: &'static str,
+    pub checks: ReadinessChecks,
+}
+
+pub async fn liveness() -> Json<HealthStatus> {
+    // This function reports that the process is up and serving requests.
+    // It deliberately touches nothing else, so a database outage does not
+    // get the server restarted.
+    Json(HealthStatus { status: "ok" })
+}
+
+pub async fn readiness(State(pool): State<PgPool>) -> (StatusCode, Json<ReadinessStatus>) {
+    // This function reports whether the server can handle traffic: the
+    // database must answer and every bundled migration must be applied.
+    let checks = match tokio::time::timeout(READINESS_TIMEOUT, check_database(&pool)).await {
+        Ok(checks) => checks,
+        Err(_) => ReadinessChecks {
+            database: "timed out".to_string(),
+            migrations: "unknown".to_string(),
+        },
+    };
+
+    let ready = checks.database == "ok" && checks.migrations == "ok";
+    let (status, label) = if ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+    (status, Json(ReadinessStatus { status: label, checks }))
+}
+
+// The probe is unauthenticated, so error details only go to the log
+fn failed(check: &str, error: sqlx::Error) -> String {
+    tracing::warn!(error = %error, check, "Readiness check failed");
+    "unavailable".to_string()
+}
+
+async fn check_database(pool: &PgPool) -> ReadinessChecks {
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            return ReadinessChecks {
+                database: failed("database", e),
+                migrations: "unknown".to_string(),
+            };
+        }
+    };
+
+    let database = match conn.ping().await {
+        Ok(()) => "ok".to_string(),
+        Err(e) => failed("database", e),
+    };
+
+    let migrations = match conn.list_applied_migrations().await {
+        Ok(applied) => {
+            let pending = MIGRATOR
+                .iter()
+                .filter(|migration| migration.migration_type.is_up_migration())
+                .filter(|migration| !applied.iter().any(|done| done.version == migration.version))
+                .count();
+            if pending == 0 {
+                "ok".to_string()
+            } else {
+                format!("{} pending", pending)
+            }
+        }
+        Err(e) => failed("migrations", e.into()),
+    };
+
+    ReadinessChecks { database, migrations }
+}
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/healthz", get(liveness))
+        .route("/readyz", get(readiness))
+        .with_state(state)
+}
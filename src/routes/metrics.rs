@@ -0,0 +1,29 @@
+//! Prometheus scrape endpoint, served outside the versioned API.
+
+use axum::{
+    extract::State,
+    http::header,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use sqlx::PgPool;
+
+use crate::metrics::Metrics;
+use crate::state::AppState;
+
+// Content type of the Prometheus text exposition format
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub async fn get_metrics(State(metrics): State<Metrics>, State(pool): State<PgPool>) -> impl IntoResponse {
+    // This function renders every metric for a Prometheus scrape. It needs
+    // no credentials, so keep it reachable only from the monitoring network
+    // or switch it off with `features.metrics`.
+    ([(header::CONTENT_TYPE, TEXT_FORMAT)], metrics.render(&pool))
+}
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .with_state(state)
+}
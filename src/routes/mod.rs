@@ -1,5 +1,12 @@
+pub mod auth;
 pub mod user;
 pub mod tome;
 pub mod entry;
+pub mod entry_revision;
+pub mod search;
+pub mod sync;
 pub mod archive;
-pub mod v1;
\ No newline at end of file
+pub mod health;
+pub mod metrics;
+pub mod v1;
+pub mod v2;
\ No newline at end of file
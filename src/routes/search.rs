@@ -0,0 +1,97 @@
+use axum::{Json, extract::State};
+use serde::Deserialize;
+use sqlx::PgPool;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::extract::Query;
+use crate::models::search::{SearchHit, SearchPathSegment};
+
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+pub async fn search(
+    auth: AuthUser,
+    Query(params): Query<SearchQuery>,
+    State(pool): State<PgPool>
+) -> Result<Json<Vec<SearchHit>>, ApiError> {
+    // This function runs a full-text search over the caller's archives,
+    // tomes and entries and returns the best matches first. `q` accepts
+    // web search syntax: quoted phrases, `or` and `-excluded` terms.
+    let q = params.q.trim();
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if q.is_empty() {
+        return Err(ApiError::bad_request("The search query `q` must not be empty"));
+    }
+    if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+        return Err(ApiError::bad_request(format!("`limit` must be between 1 and {}", MAX_SEARCH_LIMIT)));
+    }
+
+    let rows = sqlx::query!(
+        r#"WITH q AS (SELECT websearch_to_tsquery('english', $2) AS query),
+         hits AS (
+             SELECT 'entry' AS kind, e.id, e.title, e.content AS body,
+                    ts_rank(e.search_vector, q.query) AS rank,
+                    a.id AS archive_id, a.name AS archive_name, t.id AS tome_id, t.name AS tome_name
+             FROM q, entries e
+             JOIN tomes t ON t.id = e.tome_id
+             JOIN archives a ON a.id = t.archive_id
+             WHERE e.user_id = $1 AND e.deleted_at IS NULL AND e.search_vector @@ q.query
+             UNION ALL
+             SELECT 'tome', t.id, t.name, coalesce(t.description, ''),
+                    ts_rank(t.search_vector, q.query),
+                    a.id, a.name, NULL, NULL
+             FROM q, tomes t
+             JOIN archives a ON a.id = t.archive_id
+             WHERE t.user_id = $1 AND t.deleted_at IS NULL AND t.search_vector @@ q.query
+             UNION ALL
+             SELECT 'archive', a.id, a.name, coalesce(a.description, ''),
+                    ts_rank(a.search_vector, q.query),
+                    NULL, NULL, NULL, NULL
+             FROM q, archives a
+             WHERE a.user_id = $1 AND a.deleted_at IS NULL AND a.search_vector @@ q.query
+             ORDER BY rank DESC, id
+             LIMIT $3
+         )
+         -- Highlighting is the expensive part, so only the returned hits get it
+         SELECT hits.kind AS "kind!", hits.id AS "id!", hits.title AS "title!",
+                ts_headline('english', hits.body, q.query, 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10') AS "snippet!",
+                hits.rank AS "rank!",
+                hits.archive_id, hits.archive_name, hits.tome_id, hits.tome_name
+         FROM hits, q
+         ORDER BY hits.rank DESC, hits.id"#,
+        auth.user_id,
+        q,
+        limit
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let hits = rows
+        .into_iter()
+        .map(|row| {
+            let mut path = Vec::new();
+            if let (Some(id), Some(name)) = (row.archive_id, row.archive_name) {
+                path.push(SearchPathSegment { kind: "archive", id, name });
+            }
+            if let (Some(id), Some(name)) = (row.tome_id, row.tome_name) {
+                path.push(SearchPathSegment { kind: "tome", id, name });
+            }
+            SearchHit {
+                kind: row.kind,
+                id: row.id,
+                title: row.title,
+                snippet: row.snippet,
+                rank: row.rank,
+                path,
+            }
+        })
+        .collect();
+
+    Ok(Json(hits))
+}
@@ -0,0 +1,61 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, StatusCode},
+    response::IntoResponse,
+};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::extract::{Body, Query};
+use crate::metrics::Metrics;
+use crate::sync::{self, SyncQuery, SyncRequest};
+use crate::sync::protocol::{SyncProtocol, SYNC_PROTOCOL_HEADER};
+
+// Serializes the body in the negotiated version, returning its size too
+fn respond<T: Serialize>(protocol: SyncProtocol, body: T) -> Result<(usize, impl IntoResponse), ApiError> {
+    let body = protocol.encode(&body).map_err(ApiError::internal)?;
+    let bytes = serde_json::to_vec(&body).map_err(ApiError::internal)?;
+    let headers = [
+        (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+        (SYNC_PROTOCOL_HEADER, HeaderValue::from_static(protocol.as_str())),
+    ];
+    Ok((bytes.len(), (headers, bytes)))
+}
+
+pub async fn get_sync(
+    auth: AuthUser,
+    protocol: SyncProtocol,
+    Query(params): Query<SyncQuery>,
+    State(pool): State<PgPool>,
+    State(metrics): State<Metrics>,
+) -> Result<impl IntoResponse, ApiError> {
+    // This function returns the caller's changes since the given cursor,
+    // written in the negotiated protocol version.
+    let response = sync::pull(&pool, auth.user_id, params).await?;
+    metrics.record_pull(&response);
+    let (size, response) = respond(protocol, response)?;
+    metrics.record_sync_payload("pull", size);
+    Ok(response)
+}
+
+pub async fn post_sync(
+    auth: AuthUser,
+    protocol: SyncProtocol,
+    State(pool): State<PgPool>,
+    State(metrics): State<Metrics>,
+    Body(body): Body,
+) -> Result<impl IntoResponse, ApiError> {
+    // This function applies the changes a client made offline. The body is
+    // translated from the negotiated protocol version before it is parsed.
+    metrics.record_sync_payload("push", body.len());
+    let payload: Value = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, "invalid_body", format!("Invalid JSON body: {}", e)))?;
+    let payload: SyncRequest = serde_json::from_value(protocol.upgrade_request(payload))
+        .map_err(|e| ApiError::unprocessable(format!("Invalid sync payload: {}", e)))?;
+    let response = sync::push(&pool, auth.user_id, payload).await?;
+    metrics.record_push(&response.results);
+    let (_, response) = respond(protocol, response)?;
+    Ok(response)
+}
@@ -1,84 +1,332 @@
-use axum::{Json, extract::Path, extract::State};
-use sqlx::PgPool;
+use axum::{extract::{OriginalUri, State}, http::Uri};
+use serde::Deserialize;
+use sqlx::{PgPool, QueryBuilder};
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::extract::{Path, Query};
+use crate::pagination::{Columns, ListParams, Page, Paginated};
+use crate::tombstones;
+use crate::etag::{self, IfMatch, Tagged};
+use crate::sync::ItemKind;
+use crate::ordering;
+use crate::validation::{nullable, FieldErrors, ValidJson, Validate};
+use chrono::Utc;
 use crate::models::tome::Tome;
 
-pub async fn list_tomes(State(pool): State<PgPool>) -> Result<Json<Vec<Tome>>, axum::http::StatusCode> {
-    // This function retrieves all tomes from the database
-    // and returns them as a JSON response.
-    let tomes = sqlx::query_as::<_, Tome>("SELECT * FROM tomes")
-        .fetch_all(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    Ok(Json(tomes))
+#[derive(Deserialize)]
+pub struct TomeFilter {
+    pub archive_id: Option<String>,
+}
+
+/// Body for creating a tome under `/archives/:id/tomes`, where the parent
+/// comes from the path.
+#[derive(Deserialize)]
+pub struct CreateChildTomePayload {
+    /// Client-chosen id, as used by sync clients; generated when absent.
+    pub id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateTomePayload {
+    /// Client-chosen id, as used by sync clients; generated when absent.
+    pub id: Option<String>,
+    pub archive_id: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateTomePayload {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Body for `PATCH`: only the fields present are changed.
+#[derive(Deserialize)]
+pub struct PatchTomePayload {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub description: Option<Option<String>>,
+}
+
+/// Body for `POST /tomes/:id/move`.
+#[derive(Deserialize)]
+pub struct MoveTomePayload {
+    /// New parent archive; stays in the current one when absent.
+    pub archive_id: Option<String>,
+    /// Zero-based place among the archive's other tomes; the end when
+    /// absent or past the end.
+    pub position: Option<i32>,
+}
+
+impl Validate for CreateChildTomePayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.optional_id("id", self.id.as_deref());
+        errors.name("name", &self.name);
+    }
+}
+
+impl Validate for CreateTomePayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.optional_id("id", self.id.as_deref());
+        errors.id("archive_id", &self.archive_id);
+        errors.name("name", &self.name);
+    }
+}
+
+impl Validate for UpdateTomePayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+    }
+}
+
+impl Validate for PatchTomePayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.name("name", name);
+        }
+    }
+}
+
+impl Validate for MoveTomePayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.optional_id("archive_id", self.archive_id.as_deref());
+        errors.position("position", self.position);
+    }
+}
+
+async fn fetch_tomes(
+    pool: &PgPool,
+    auth: &AuthUser,
+    archive_id: Option<String>,
+    params: ListParams,
+    uri: &Uri,
+) -> Result<Page<Tome>, ApiError> {
+    let list = params.resolve()?;
+
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM tomes WHERE user_id = ", Tome::COLUMNS));
+    query.push_bind(auth.user_id).push(" AND deleted_at IS NULL");
+    if let Some(archive_id) = archive_id {
+        query.push(" AND archive_id = ").push_bind(archive_id);
+    }
+    list.apply(&mut query, &Columns::DEFAULT);
+
+    let tomes = query.build_query_as::<Tome>().fetch_all(pool).await?;
+
+    Ok(list.page(tomes, uri))
+}
+
+pub async fn list_tomes(
+    auth: AuthUser,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<ListParams>,
+    Query(filter): Query<TomeFilter>,
+    State(pool): State<PgPool>
+) -> Result<Page<Tome>, ApiError> {
+    // This function retrieves one page of the caller's tomes, optionally
+    // limited to one archive, and returns it as a JSON response.
+    fetch_tomes(&pool, &auth, filter.archive_id, params, &uri).await
+}
+
+pub async fn list_archive_tomes(
+    auth: AuthUser,
+    Path(archive_id): Path<String>,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<ListParams>,
+    State(pool): State<PgPool>
+) -> Result<Page<Tome>, ApiError> {
+    // This function retrieves one page of the tomes in one of the caller's
+    // archives and returns it as a JSON response.
+    sqlx::query!(
+        "SELECT id FROM archives WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        archive_id,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("archive"))?;
+
+    fetch_tomes(&pool, &auth, Some(archive_id), params, &uri).await
 }
 
 pub async fn create_tome(
+    auth: AuthUser,
     State(pool): State<PgPool>,
-    Json(payload): Json<Tome>,
-) -> Result<Json<Tome>, axum::http::StatusCode> {
+    ValidJson(payload): ValidJson<CreateTomePayload>,
+) -> Result<Tagged<Tome>, ApiError> {
     // This function creates a new tome in the database
     // using the provided JSON payload and returns the created tome.
-    let new_tome = sqlx::query_as::<_, Tome>(
-        "INSERT INTO tomes (id, archive_id, user_id, name, description, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP) RETURNING *"
+    // The parent archive must belong to the caller.
+    let new_tome = sqlx::query_as!(
+        Tome,
+        "INSERT INTO tomes (id, archive_id, user_id, name, description, position, created_at, updated_at) SELECT $1, id, $3, $4, $5, COALESCE((SELECT MAX(position) + (MAX(position) < $6)::int FROM tomes WHERE archive_id = $2 AND deleted_at IS NULL), 0), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP FROM archives WHERE id = $2 AND user_id = $3 AND deleted_at IS NULL RETURNING id, archive_id, user_id, name, description, created_at, updated_at, position, revision",
+        payload.id.unwrap_or_else(|| format!("tome-{}", uuid::Uuid::new_v4())),
+        payload.archive_id,
+        auth.user_id,
+        payload.name,
+        payload.description,
+        ordering::MAX_POSITION
     )
-    .bind(&payload.id)
-    .bind(&payload.archive_id)
-    .bind(&payload.user_id)
-    .bind(&payload.name)
-    .bind(&payload.description)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("archive"))?;
     
-    Ok(Json(new_tome))
+    Ok(Tagged(new_tome))
+}
+
+pub async fn create_archive_tome(
+    auth: AuthUser,
+    Path(archive_id): Path<String>,
+    State(pool): State<PgPool>,
+    ValidJson(payload): ValidJson<CreateChildTomePayload>,
+) -> Result<Tagged<Tome>, ApiError> {
+    // This function creates a new tome inside one of the caller's archives
+    // and returns the created tome.
+    let new_tome = sqlx::query_as!(
+        Tome,
+        "INSERT INTO tomes (id, archive_id, user_id, name, description, position, created_at, updated_at) SELECT $1, id, $3, $4, $5, COALESCE((SELECT MAX(position) + (MAX(position) < $6)::int FROM tomes WHERE archive_id = $2 AND deleted_at IS NULL), 0), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP FROM archives WHERE id = $2 AND user_id = $3 AND deleted_at IS NULL RETURNING id, archive_id, user_id, name, description, created_at, updated_at, position, revision",
+        payload.id.unwrap_or_else(|| format!("tome-{}", uuid::Uuid::new_v4())),
+        archive_id,
+        auth.user_id,
+        payload.name,
+        payload.description,
+        ordering::MAX_POSITION
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("archive"))?;
+
+    Ok(Tagged(new_tome))
 }
 
 pub async fn update_tome(
+    auth: AuthUser,
     Path(id): Path<String>,
+    if_match: IfMatch,
     State(pool): State<PgPool>,
-    Json(payload): Json<Tome>
-) -> Result<Json<Tome>, axum::http::StatusCode> {
+    ValidJson(payload): ValidJson<UpdateTomePayload>
+) -> Result<Tagged<Tome>, ApiError> {
     // This function updates an existing tome in the database
     // using the provided ID and JSON payload, returning the updated tome.
-    let updated_tome = sqlx::query_as::<_, Tome>(
-        "UPDATE tomes SET name = $1, description = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3 RETURNING *"
+    // A stale If-Match is rejected with 412.
+    let updated_tome = sqlx::query_as!(
+        Tome,
+        "UPDATE tomes SET name = $1, description = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3 AND user_id = $4 AND deleted_at IS NULL AND ($5::bigint[] IS NULL OR revision = ANY($5)) RETURNING id, archive_id, user_id, name, description, created_at, updated_at, position, revision",
+        payload.name,
+        payload.description,
+        id,
+        auth.user_id,
+        if_match.revisions()
     )
-    .bind(&payload.name)
-    .bind(&payload.description)
-    .bind(id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
-    
-    Ok(Json(updated_tome))
+    .fetch_optional(&pool)
+    .await?;
+
+    match updated_tome {
+        Some(tome) => Ok(Tagged(tome)),
+        None => Err(etag::write_failed(&pool, ItemKind::Tome, &id, auth.user_id).await),
+    }
+}
+
+pub async fn patch_tome(
+    auth: AuthUser,
+    Path(id): Path<String>,
+    if_match: IfMatch,
+    State(pool): State<PgPool>,
+    ValidJson(payload): ValidJson<PatchTomePayload>
+) -> Result<Tagged<Tome>, ApiError> {
+    // This function changes only the fields present in the JSON payload
+    // and returns the updated tome. A stale If-Match is rejected with 412.
+    let updated_tome = sqlx::query_as!(
+        Tome,
+        "UPDATE tomes SET name = COALESCE($1, name), description = CASE WHEN $2 THEN $3 ELSE description END, updated_at = CURRENT_TIMESTAMP WHERE id = $4 AND user_id = $5 AND deleted_at IS NULL AND ($6::bigint[] IS NULL OR revision = ANY($6)) RETURNING id, archive_id, user_id, name, description, created_at, updated_at, position, revision",
+        payload.name,
+        payload.description.is_some(),
+        payload.description.flatten(),
+        id,
+        auth.user_id,
+        if_match.revisions()
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    match updated_tome {
+        Some(tome) => Ok(Tagged(tome)),
+        None => Err(etag::write_failed(&pool, ItemKind::Tome, &id, auth.user_id).await),
+    }
+}
+
+pub async fn move_tome(
+    auth: AuthUser,
+    Path(id): Path<String>,
+    if_match: IfMatch,
+    State(pool): State<PgPool>,
+    ValidJson(payload): ValidJson<MoveTomePayload>
+) -> Result<Tagged<Tome>, ApiError> {
+    // This function moves a tome to another of the caller's archives
+    // and/or to a new place among its siblings, and returns the moved tome.
+    // A stale If-Match is rejected with 412.
+    let mut tx = pool.begin().await?;
+    ordering::move_item(
+        &mut tx,
+        ordering::Siblings::Tomes,
+        auth.user_id,
+        &id,
+        payload.archive_id.as_deref(),
+        payload.position,
+        if_match.revisions(),
+    )
+    .await?;
+
+    let moved_tome = sqlx::query_as!(
+        Tome,
+        "SELECT id, archive_id, user_id, name, description, created_at, updated_at, position, revision FROM tomes WHERE id = $1 AND user_id = $2",
+        id,
+        auth.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Tagged(moved_tome))
 }
 
-pub async fn get_tome( 
+pub async fn get_tome(
+    auth: AuthUser,
     Path(id): Path<String>,
     State(pool): State<PgPool>
-) -> Result<Json<Tome>, axum::http::StatusCode> {
+) -> Result<Tagged<Tome>, ApiError> {
     // This function retrieves a specific tome by its ID from the database
     // and returns it as a JSON response.
-    let tome = sqlx::query_as::<_, Tome>("SELECT * FROM tomes WHERE id = $1")
-        .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
+    let tome = sqlx::query_as!(
+        Tome,
+        "SELECT id, archive_id, user_id, name, description, created_at, updated_at, position, revision FROM tomes WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        auth.user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("tome"))?;
     
-    Ok(Json(tome))
+    Ok(Tagged(tome))
 }
 
 pub async fn delete_tome(
+    auth: AuthUser,
     Path(id): Path<String>,
     State(pool): State<PgPool>
-) -> Result<(), axum::http::StatusCode> {
-    // This function deletes a tome by its ID from the database.
-    sqlx::query("DELETE FROM tomes WHERE id = $1")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
+) -> Result<(), ApiError> {
+    // This function deletes a tome by its ID, leaving a tombstone for
+    // sync clients and cascading to everything beneath it.
+    let mut tx = pool.begin().await?;
+    let deleted = tombstones::delete_tome(&mut tx, auth.user_id, &id, Utc::now())
+        .await?;
+
+    if !deleted {
+        return Err(ApiError::not_found("tome"));
+    }
+    tx.commit().await?;
     
     Ok(())
 }
\ No newline at end of file
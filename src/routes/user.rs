@@ -1,84 +1,168 @@
-use axum::{Json, extract::Path, extract::State};
-use sqlx::PgPool;
-use crate::models::user::User;
+use axum::{Json, extract::{OriginalUri, State}};
+use serde::Deserialize;
+use sqlx::{PgPool, QueryBuilder};
+use crate::auth::{AdminUser, AuthUser, hash_password, is_admin};
+use crate::error::ApiError;
+use crate::extract::{Path, Query};
+use crate::pagination::{Columns, ListParams, Page, Paginated};
+use crate::models::user::UserProfile;
+use crate::validation::{FieldErrors, ValidJson, Validate};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct CreateUserPayload {
+    pub username: String,
+    pub email: String,
+    /// Stored as an argon2 hash, never as given.
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateUserPayload {
+    pub username: String,
+    pub email: String,
+}
+
+impl Validate for CreateUserPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.username("username", self.username.trim());
+        errors.email("email", self.email.trim());
+        errors.password("password", &self.password);
+    }
+}
+
+impl Validate for UpdateUserPayload {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.username("username", self.username.trim());
+        errors.email("email", self.email.trim());
+    }
+}
+
+/// Only the account owner may change or remove an account; any other
+/// account is reported as missing.
+fn ensure_self(auth: &AuthUser, id: Uuid) -> Result<(), ApiError> {
+    if auth.user_id == id {
+        Ok(())
+    } else {
+        Err(ApiError::not_found("user"))
+    }
+}
+
+async fn fetch_profile(pool: &PgPool, id: Uuid) -> Result<UserProfile, ApiError> {
+    sqlx::query_as!(
+        UserProfile,
+        "SELECT id, username, email, created_at, updated_at, is_active, is_admin FROM users WHERE id = $1",
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::not_found("user"))
+}
 
 pub async fn list_users(
+    _admin: AdminUser,
+    OriginalUri(uri): OriginalUri,
+    Query(params): Query<ListParams>,
     State(pool): State<PgPool>
-) -> Result<Json<Vec<User>>, axum::http::StatusCode> {
-    // This function retrieves all users from the database
-    // and returns them as a JSON response.
-    let users = sqlx::query_as::<_, User>("SELECT * FROM users")
-        .fetch_all(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    Ok(Json(users))
+) -> Result<Page<UserProfile>, ApiError> {
+    // This function retrieves one page of every account on the server.
+    // Only administrators may call it.
+    let list = params.resolve()?;
+
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM users WHERE TRUE", UserProfile::COLUMNS));
+    list.apply(&mut query, &Columns { name: "username", id: "id" });
+
+    let users = query.build_query_as::<UserProfile>().fetch_all(&pool).await?;
+
+    Ok(list.page(users, &uri))
 }
 
 pub async fn create_user(
+    _admin: AdminUser,
     State(pool): State<PgPool>,
-    Json(payload): Json<User>
-) -> Result<Json<User>, axum::http::StatusCode> {
+    ValidJson(payload): ValidJson<CreateUserPayload>
+) -> Result<Json<UserProfile>, ApiError> {
     // This function creates a new user in the database
     // using the provided JSON payload and returns the created user.
-    let new_user = sqlx::query_as::<_, User>(
-        "INSERT INTO users (id, username, email, password_hash, created_at, updated_at, is_active) VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, true) RETURNING *"
+    // Only administrators may call it, whether or not registration is open.
+    let password_hash = hash_password(payload.password).await?;
+
+    let new_user = sqlx::query_as!(
+        UserProfile,
+        "INSERT INTO users (id, username, email, password_hash, created_at, updated_at, is_active) VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, true) RETURNING id, username, email, created_at, updated_at, is_active, is_admin",
+        Uuid::new_v4(),
+        payload.username.trim(),
+        payload.email.trim(),
+        password_hash
     )
-    .bind(&payload.id)
-    .bind(&payload.username)
-    .bind(&payload.email)
-    .bind(&payload.password_hash)
     .fetch_one(&pool)
-    .await
-    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await?;
     
     Ok(Json(new_user))
 }
 
 pub async fn update_user(
-    Path(id): Path<String>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
     State(pool): State<PgPool>,
-    Json(payload): Json<User>
-) -> Result<Json<User>, axum::http::StatusCode> {
+    ValidJson(payload): ValidJson<UpdateUserPayload>
+) -> Result<Json<UserProfile>, ApiError> {
     // This function updates an existing user in the database
     // using the provided ID and JSON payload, returning the updated user.
-    let updated_user = sqlx::query_as::<_, User>(
-        "UPDATE users SET username = $1, email = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3 RETURNING *"
+    ensure_self(&auth, id)?;
+
+    let updated_user = sqlx::query_as!(
+        UserProfile,
+        "UPDATE users SET username = $1, email = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $3 RETURNING id, username, email, created_at, updated_at, is_active, is_admin",
+        payload.username.trim(),
+        payload.email.trim(),
+        id
     )
-    .bind(&payload.username)
-    .bind(&payload.email)
-    .bind(id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(ApiError::not_found("user"))?;
     
     Ok(Json(updated_user))
 }
 
+pub async fn get_current_user(
+    auth: AuthUser,
+    State(pool): State<PgPool>
+) -> Result<Json<UserProfile>, ApiError> {
+    // This function returns the caller's own account.
+    Ok(Json(fetch_profile(&pool, auth.user_id).await?))
+}
+
 pub async fn get_user(
-    Path(id): Path<String>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
     State(pool): State<PgPool>
-) -> Result<Json<User>, axum::http::StatusCode> {
-    // This function retrieves a user by their ID from the database.
-    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
-        .bind(id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
-    
-    Ok(Json(user))
+) -> Result<Json<UserProfile>, ApiError> {
+    // This function retrieves a user by their ID from the database. Other
+    // callers than the owner or an administrator get the public profile.
+    let user = fetch_profile(&pool, id).await?;
+
+    if auth.user_id == id || is_admin(&pool, auth.user_id).await? {
+        Ok(Json(user))
+    } else {
+        Ok(Json(user.public()))
+    }
 }
 
 pub async fn delete_user(
-    Path(id): Path<String>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
     State(pool): State<PgPool>
-) -> Result<axum::http::StatusCode, axum::http::StatusCode> {
+) -> Result<axum::http::StatusCode, ApiError> {
     // This function deletes a user by their ID from the database.
-    sqlx::query("DELETE FROM users WHERE id = $1")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    ensure_self(&auth, id)?;
+
+    sqlx::query!(
+        "DELETE FROM users WHERE id = $1",
+        id
+    )
+    .execute(&pool)
+    .await?;
     
     Ok(axum::http::StatusCode::NO_CONTENT)
 }
@@ -1,13 +1,16 @@
 // src/routes/v1/user.rs
 use axum::{Router, routing::get};
-use sqlx::PgPool;
+use crate::state::AppState;
 use crate::routes::archive::{
-    create_archive, delete_archive, get_archive, list_archives, update_archive
+    create_archive, delete_archive, get_archive, get_archive_tree, list_archives, patch_archive, update_archive
 };
+use crate::routes::tome::{create_archive_tome, list_archive_tomes};
 
-pub fn routes(pool: PgPool) -> Router {
+pub fn routes(state: AppState) -> Router {
     Router::new()
         .route("/", get(list_archives).post(create_archive))
-        .route("/:id", get(get_archive).put(update_archive).delete(delete_archive))
-        .with_state(pool)
+        .route("/:id", get(get_archive).put(update_archive).patch(patch_archive).delete(delete_archive))
+        .route("/:id/tomes", get(list_archive_tomes).post(create_archive_tome))
+        .route("/:id/tree", get(get_archive_tree))
+        .with_state(state)
 }
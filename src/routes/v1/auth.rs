@@ -0,0 +1,11 @@
+// src/routes/v1/auth.rs
+use axum::{Router, routing::post};
+use crate::routes::auth::{login, register};
+use crate::state::AppState;
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/register", post(register))
+        .route("/login", post(login))
+        .with_state(state)
+}
@@ -1,13 +1,21 @@
 // src/routes/v1/user.rs
-use axum::{Router, routing::get};
-use sqlx::PgPool;
+use axum::{Router, routing::{get, post}};
+use crate::state::AppState;
 use crate::routes::entry::{
-    create_entry, delete_entry, get_entry, list_entries, update_entry
+    create_entry, delete_entry, get_entry, list_entries, move_entry, patch_entry, update_entry
+};
+use crate::routes::entry_revision::{
+    diff_revision, get_revision, list_revisions, restore_revision
 };
 
-pub fn routes(pool: PgPool) -> Router {
+pub fn routes(state: AppState) -> Router {
     Router::new()
         .route("/", get(list_entries).post(create_entry))
-        .route("/:id", get(get_entry).put(update_entry).delete(delete_entry))
-        .with_state(pool)
+        .route("/:id", get(get_entry).put(update_entry).patch(patch_entry).delete(delete_entry))
+        .route("/:id/move", post(move_entry))
+        .route("/:id/revisions", get(list_revisions))
+        .route("/:id/revisions/:rev", get(get_revision))
+        .route("/:id/revisions/:rev/diff", get(diff_revision))
+        .route("/:id/revisions/:rev/restore", post(restore_revision))
+        .with_state(state)
 }
@@ -1,17 +1,25 @@
 use axum::{Router};
-use sqlx::PgPool;
+use crate::rate_limit::{self, Scope};
+use crate::state::AppState;
 
+pub mod auth;
 pub mod user;
 pub mod archive;
 pub mod tome;
 pub mod entry;
+pub mod search;
 pub mod sync;
 
-pub fn create_v1_routes(pool: PgPool) -> Router {
+pub fn create_v1_routes(state: AppState) -> Router {
+    let crud = Router::new()
+        .nest("/users", user::routes(state.clone()))
+        .nest("/archives", archive::routes(state.clone()))
+        .nest("/tomes", tome::routes(state.clone()))
+        .nest("/entries", entry::routes(state.clone()))
+        .nest("/search", search::routes(state.clone()));
+
     Router::new()
-        .nest("/users", user::routes(pool.clone()))
-        .nest("/archives", archive::routes(pool.clone()))
-        .nest("/tomes", tome::routes(pool.clone()))
-        .nest("/entries", entry::routes(pool.clone()))
-        .nest("/sync", sync::create_sync_routes(pool))
+        .nest("/auth", rate_limit::limit(&state, Scope::Auth, auth::routes(state.clone())))
+        .merge(rate_limit::limit(&state, Scope::Crud, crud))
+        .nest("/sync", rate_limit::limit(&state, Scope::Sync, sync::create_sync_routes(state.clone())))
 }
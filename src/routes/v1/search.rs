@@ -0,0 +1,10 @@
+// src/routes/v1/search.rs
+use axum::{Router, routing::get};
+use crate::state::AppState;
+use crate::routes::search::search;
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(search))
+        .with_state(state)
+}
@@ -1,13 +1,16 @@
 // src/routes/v1/user.rs
-use axum::{Router, routing::get};
-use sqlx::PgPool;
+use axum::{Router, routing::{get, post}};
+use crate::state::AppState;
 use crate::routes::tome::{
-    create_tome, delete_tome, get_tome, list_tomes, update_tome
+    create_tome, delete_tome, get_tome, list_tomes, move_tome, patch_tome, update_tome
 };
+use crate::routes::entry::{create_tome_entry, list_tome_entries};
 
-pub fn routes(pool: PgPool) -> Router {
+pub fn routes(state: AppState) -> Router {
     Router::new()
         .route("/", get(list_tomes).post(create_tome))
-        .route("/:id", get(get_tome).put(update_tome).delete(delete_tome))
-        .with_state(pool)
+        .route("/:id", get(get_tome).put(update_tome).patch(patch_tome).delete(delete_tome))
+        .route("/:id/move", post(move_tome))
+        .route("/:id/entries", get(list_tome_entries).post(create_tome_entry))
+        .with_state(state)
 }
@@ -1,13 +1,14 @@
 // src/routes/v1/user.rs
 use axum::{Router, routing::get};
-use sqlx::PgPool;
+use crate::state::AppState;
 use crate::routes::user::{
-    create_user, delete_user, get_user, list_users, update_user
+    create_user, delete_user, get_current_user, get_user, list_users, update_user
 };
 
-pub fn routes(pool: PgPool) -> Router {
+pub fn routes(state: AppState) -> Router {
     Router::new()
         .route("/", get(list_users).post(create_user))
+        .route("/me", get(get_current_user))
         .route("/:id", get(get_user).put(update_user).delete(delete_user))
-        .with_state(pool)
+        .with_state(state)
 }
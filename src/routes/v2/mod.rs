@@ -0,0 +1,12 @@
+use axum::Router;
+use crate::rate_limit::{self, Scope};
+use crate::state::AppState;
+
+pub mod sync;
+
+// Only the sync protocol has changed so far; everything else is served
+// from /api/v1
+pub fn create_v2_routes(state: AppState) -> Router {
+    Router::new()
+        .nest("/sync", rate_limit::limit(&state, Scope::Sync, sync::create_sync_routes(state.clone())))
+}
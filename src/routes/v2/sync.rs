@@ -0,0 +1,12 @@
+// src/routes/v2/sync.rs
+use axum::{Extension, Router, routing::get};
+use crate::routes::sync::{get_sync, post_sync};
+use crate::state::AppState;
+use crate::sync::protocol::SyncProtocol;
+
+pub fn create_sync_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(get_sync).post(post_sync))
+        .layer(Extension(SyncProtocol::V2))
+        .with_state(state)
+}
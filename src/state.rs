@@ -0,0 +1,41 @@
+use axum::extract::FromRef;
+use sqlx::PgPool;
+
+use crate::auth::AuthKeys;
+use crate::config::Features;
+use crate::metrics::Metrics;
+use crate::rate_limit::RateLimiter;
+
+/// Shared state handed to every router.
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: PgPool,
+    pub auth: AuthKeys,
+    pub features: Features,
+    pub metrics: Metrics,
+    pub rate_limiter: RateLimiter,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for AuthKeys {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth.clone()
+    }
+}
+
+impl FromRef<AppState> for Features {
+    fn from_ref(state: &AppState) -> Self {
+        state.features
+    }
+}
+
+impl FromRef<AppState> for Metrics {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
@@ -0,0 +1,989 @@
+//! Server side of the offline sync protocol shared by every API version.
+//!
+//! The types here are the canonical (current) shape of the wire format.
+//! Older protocol versions are translated to and from it in [`protocol`].
+
+pub mod protocol;
+
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc, NaiveDateTime};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use tracing::Instrument;
+use axum::http::StatusCode;
+use crate::error::ApiError;
+use crate::merge::{merge3, MergeOutcome};
+use crate::ordering::MAX_POSITION;
+use crate::tombstones;
+use crate::validation::{FieldErrors, Validate};
+
+// Page sizes for GET /sync, counted in rows across all kinds
+const DEFAULT_PAGE_SIZE: i64 = 500;
+const MAX_PAGE_SIZE: i64 = 2000;
+
+/// The kinds of item a client syncs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Archive,
+    Tome,
+    Entry,
+}
+
+impl ItemKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Archive => "archive",
+            Self::Tome => "tome",
+            Self::Entry => "entry",
+        }
+    }
+
+    // Span for work on one pushed item
+    fn span(self, id: &str) -> tracing::Span {
+        tracing::info_span!("sync_item", kind = self.as_str(), id)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncArchive {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncTome {
+    pub id: String,
+    pub archive_id: String,
+    pub name: String, // Sent as `title` by protocol v1 clients
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Order within the archive. Pushes without it keep the tome's place,
+    /// or append it when it is new or moved to another archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncEntry {
+    pub id: String,
+    pub tome_id: String,
+    pub title: String,
+    pub content: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Order within the tome, with the same defaults as for tomes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+    /// Server revision of this version of the entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<i64>,
+    /// Revision the client's edit started from. When present, concurrent
+    /// server-side edits are merged instead of overwritten.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_revision: Option<i64>,
+}
+
+// Pushed rows are checked against the column limits so one bad item is
+// rejected on its own instead of failing the whole batch
+impl Validate for SyncArchive {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.id("id", &self.id);
+        errors.max_length("name", &self.name);
+    }
+}
+
+impl Validate for SyncTome {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.id("id", &self.id);
+        errors.id("archive_id", &self.archive_id);
+        errors.max_length("name", &self.name);
+        errors.position("position", self.position);
+    }
+}
+
+impl Validate for SyncEntry {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.id("id", &self.id);
+        errors.id("tome_id", &self.tome_id);
+        errors.max_length("title", &self.title);
+        errors.position("position", self.position);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncDeletion {
+    pub id: String,
+    pub deleted_at: String,
+}
+
+/// Tombstones per kind. Deleting a parent implies deleting its children,
+/// but the server still reports every affected id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncDeletions {
+    #[serde(default)]
+    pub archives: Vec<SyncDeletion>,
+    #[serde(default)]
+    pub tomes: Vec<SyncDeletion>,
+    #[serde(default)]
+    pub entries: Vec<SyncDeletion>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    pub archives: Vec<SyncArchive>,
+    pub tomes: Vec<SyncTome>,
+    pub entries: Vec<SyncEntry>,
+    pub deleted: SyncDeletions,
+    /// Token to send back as `cursor` on the next pull.
+    pub cursor: String,
+    /// More changes are waiting; pull again with `cursor` right away.
+    pub has_more: bool,
+    #[serde(rename = "lastModified")]
+    pub last_modified: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncRequest {
+    pub archives: Vec<SyncArchive>,
+    pub tomes: Vec<SyncTome>,
+    pub entries: Vec<SyncEntry>,
+    #[serde(default)]
+    pub deleted: SyncDeletions,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncItemStatus {
+    Applied,
+    /// The pushed edit was combined with concurrent server-side edits.
+    Merged,
+    /// The pushed edit overlapped a concurrent one. The server kept its
+    /// version and stored the pushed one as a separate conflict copy.
+    Conflict,
+    /// The server already holds a version at least as new as the pushed one.
+    Stale,
+    Rejected,
+}
+
+impl SyncItemStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Applied => "applied",
+            Self::Merged => "merged",
+            Self::Conflict => "conflict",
+            Self::Stale => "stale",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// Outcome of one pushed item. Stale, merged and conflicting items carry
+/// the server's current version so the client can catch up.
+#[derive(Debug, Serialize)]
+pub struct SyncItemResult<T> {
+    pub id: String,
+    pub status: SyncItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict_copy: Option<T>,
+}
+
+impl<T> SyncItemResult<T> {
+    fn new(id: String, status: SyncItemStatus) -> Self {
+        Self { id, status, reason: None, current: None, conflict_copy: None }
+    }
+
+    fn applied(id: String) -> Self {
+        Self::new(id, SyncItemStatus::Applied)
+    }
+
+    fn merged(id: String, current: T) -> Self {
+        Self { current: Some(current), ..Self::new(id, SyncItemStatus::Merged) }
+    }
+
+    fn conflict(id: String, current: T, conflict_copy: T) -> Self {
+        Self {
+            current: Some(current),
+            conflict_copy: Some(conflict_copy),
+            ..Self::new(id, SyncItemStatus::Conflict)
+        }
+    }
+
+    fn stale(id: String, current: T) -> Self {
+        Self { current: Some(current), ..Self::new(id, SyncItemStatus::Stale) }
+    }
+
+    fn rejected(id: String, reason: &'static str) -> Self {
+        Self { reason: Some(reason), ..Self::new(id, SyncItemStatus::Rejected) }
+    }
+}
+
+impl SyncItemResult<()> {
+    fn deletion(id: String, deleted: bool) -> Self {
+        if deleted {
+            Self::applied(id)
+        } else {
+            Self::rejected(id, "not_found")
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncDeletionResults {
+    pub archives: Vec<SyncItemResult<()>>,
+    pub tomes: Vec<SyncItemResult<()>>,
+    pub entries: Vec<SyncItemResult<()>>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncPushResults {
+    pub archives: Vec<SyncItemResult<SyncArchive>>,
+    pub tomes: Vec<SyncItemResult<SyncTome>>,
+    pub entries: Vec<SyncItemResult<SyncEntry>>,
+    pub deleted: SyncDeletionResults,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncPushResponse {
+    // Kept as a string for clients written against the original response
+    pub success: &'static str,
+    #[serde(rename = "lastModified")]
+    pub last_modified: String,
+    pub results: SyncPushResults,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    pub cursor: Option<String>,
+    /// Wall-clock filter kept for clients that predate `cursor`.
+    pub since: Option<String>,
+    /// Maximum number of changes (rows and tombstones) in one page.
+    pub limit: Option<i64>,
+}
+
+/// One row of a pull page, tagged with its kind so pages can be cut across
+/// archives, tomes and entries in revision order.
+enum SyncChange {
+    Archive(SyncArchive),
+    Tome(SyncTome),
+    Entry(SyncEntry),
+    DeletedArchive(SyncDeletion),
+    DeletedTome(SyncDeletion),
+    DeletedEntry(SyncDeletion),
+}
+
+/// Opaque position in a user's change sequence. Clients must treat the
+/// encoded token as a black box so the representation can change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncCursor {
+    revision: i64,
+}
+
+impl SyncCursor {
+    const PREFIX: &'static str = "rev:";
+
+    pub fn new(revision: i64) -> Self {
+        Self { revision }
+    }
+
+    pub fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}{}", Self::PREFIX, self.revision))
+    }
+
+    pub fn decode(token: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(token).ok()?;
+        let text = String::from_utf8(bytes).ok()?;
+        let revision = text.strip_prefix(Self::PREFIX)?.parse().ok()?;
+        (revision >= 0).then_some(Self { revision })
+    }
+}
+
+/// Returns the next page of the user's changes after `params.cursor`.
+pub async fn pull(pool: &PgPool, user_id: Uuid, params: SyncQuery) -> Result<SyncResponse, ApiError> {
+    // A cursor takes precedence; without one, fall back to the legacy
+    // since parameter or a very old timestamp
+    let cursor = match params.cursor.as_deref().filter(|token| !token.is_empty()) {
+        Some(token) => Some(SyncCursor::decode(token).ok_or_else(|| ApiError::bad_request("Invalid sync cursor"))?),
+        None => None,
+    };
+    let after = cursor.unwrap_or(SyncCursor::new(0));
+    let since_timestamp: DateTime<Utc> = params
+        .since
+        .and_then(|since| since.parse().ok())
+        .unwrap_or(DateTime::<Utc>::UNIX_EPOCH);
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ApiError::bad_request(format!("`limit` must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    // Read the counter and the rows from one snapshot so the returned
+    // cursor covers exactly the changes included in this response
+    let mut tx = pool.begin().await?;
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .execute(&mut *tx)
+        .await?;
+
+    let user = sqlx::query!("SELECT sync_revision, purged_through FROM users WHERE id = $1", user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let current_revision = user.sync_revision;
+
+    // Deletions newer than the cursor may have been purged since, so the
+    // client cannot catch up incrementally and has to pull from scratch
+    if cursor.is_some_and(|cursor| cursor.revision() < user.purged_through) {
+        return Err(ApiError::new(
+            StatusCode::GONE,
+            "resync_required",
+            "Deletions since this cursor have been purged; pull again without a cursor",
+        ));
+    }
+
+    // Each kind contributes at most limit + 1 rows, so the lowest `limit`
+    // revisions across all three are always among them
+    let mut changes: Vec<(i64, SyncChange)> = Vec::new();
+
+    let rows = sqlx::query_as!(
+        ArchiveRow,
+        "SELECT id, name, description, created_at, updated_at, deleted_at, revision
+         FROM archives 
+         WHERE user_id = $1 AND revision > $2 AND updated_at > $3
+         ORDER BY revision
+         LIMIT $4",
+        user_id,
+        after.revision(),
+        since_timestamp.naive_utc(),
+        limit + 1
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for row in rows {
+        let revision = row.revision;
+        let change = match row.deleted_at {
+            Some(deleted_at) => SyncChange::DeletedArchive(deletion(row.id, deleted_at)),
+            None => SyncChange::Archive(row.into()),
+        };
+        changes.push((revision, change));
+    }
+
+    let rows = sqlx::query_as!(
+        TomeRow,
+        "SELECT id, archive_id, name, description, position, created_at, updated_at, deleted_at, revision
+         FROM tomes 
+         WHERE user_id = $1 AND revision > $2 AND updated_at > $3
+         ORDER BY revision
+         LIMIT $4",
+        user_id,
+        after.revision(),
+        since_timestamp.naive_utc(),
+        limit + 1
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for row in rows {
+        let revision = row.revision;
+        let change = match row.deleted_at {
+            Some(deleted_at) => SyncChange::DeletedTome(deletion(row.id, deleted_at)),
+            None => SyncChange::Tome(row.into()),
+        };
+        changes.push((revision, change));
+    }
+
+    let rows = sqlx::query_as!(
+        EntryRow,
+        "SELECT id, tome_id, title, content, position, created_at, updated_at, deleted_at, revision
+         FROM entries 
+         WHERE user_id = $1 AND revision > $2 AND updated_at > $3
+         ORDER BY revision
+         LIMIT $4",
+        user_id,
+        after.revision(),
+        since_timestamp.naive_utc(),
+        limit + 1
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for row in rows {
+        let revision = row.revision;
+        let change = match row.deleted_at {
+            Some(deleted_at) => SyncChange::DeletedEntry(deletion(row.id, deleted_at)),
+            None => SyncChange::Entry(row.into()),
+        };
+        changes.push((revision, change));
+    }
+
+    tx.commit().await?;
+
+    changes.sort_by_key(|(revision, _)| *revision);
+    let has_more = changes.len() > limit as usize;
+    changes.truncate(limit as usize);
+
+    // Mid-sequence pages resume right after the last change they carry;
+    // the final page hands out the user's current revision
+    let cursor = match changes.last() {
+        Some((revision, _)) if has_more => SyncCursor::new(*revision),
+        _ => SyncCursor::new(current_revision),
+    };
+
+    let mut archives = Vec::new();
+    let mut tomes = Vec::new();
+    let mut entries = Vec::new();
+    let mut deleted = SyncDeletions::default();
+    for (_, change) in changes {
+        match change {
+            SyncChange::Archive(archive) => archives.push(archive),
+            SyncChange::Tome(tome) => tomes.push(tome),
+            SyncChange::Entry(entry) => entries.push(entry),
+            SyncChange::DeletedArchive(deletion) => deleted.archives.push(deletion),
+            SyncChange::DeletedTome(deletion) => deleted.tomes.push(deletion),
+            SyncChange::DeletedEntry(deletion) => deleted.entries.push(deletion),
+        }
+    }
+
+    let last_modified = Utc::now().to_rfc3339();
+
+    Ok(SyncResponse {
+        archives,
+        tomes,
+        entries,
+        deleted,
+        cursor: cursor.encode(),
+        has_more,
+        last_modified,
+    })
+}
+
+/// Applies a client's offline changes and reports the outcome per item.
+pub async fn push(pool: &PgPool, user_id: Uuid, payload: SyncRequest) -> Result<SyncPushResponse, ApiError> {
+    let mut tx = pool.begin().await?;
+    let mut results = SyncPushResults::default();
+
+    // Each item is applied inside a span naming it, so anything logged
+    // while it fails says which item it was
+    for archive in payload.archives {
+        let span = ItemKind::Archive.span(&archive.id);
+        results.archives.push(push_archive(&mut tx, user_id, archive).instrument(span).await?);
+    }
+
+    for tome in payload.tomes {
+        let span = ItemKind::Tome.span(&tome.id);
+        results.tomes.push(push_tome(&mut tx, user_id, tome).instrument(span).await?);
+    }
+
+    for entry in payload.entries {
+        let span = ItemKind::Entry.span(&entry.id);
+        results.entries.push(push_entry(&mut tx, user_id, entry).instrument(span).await?);
+    }
+
+    // Process deletions last so an item created and deleted offline in the
+    // same batch ends up as a tombstone
+    let deletions = [
+        (ItemKind::Archive, payload.deleted.archives, &mut results.deleted.archives),
+        (ItemKind::Tome, payload.deleted.tomes, &mut results.deleted.tomes),
+        (ItemKind::Entry, payload.deleted.entries, &mut results.deleted.entries),
+    ];
+    for (kind, deletions, results) in deletions {
+        for deletion in deletions {
+            let span = kind.span(&deletion.id);
+            results.push(push_deletion(&mut tx, user_id, kind, deletion).instrument(span).await?);
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(SyncPushResponse {
+        success: "true",
+        last_modified: Utc::now().to_rfc3339(),
+        results,
+    })
+}
+
+async fn push_archive(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    archive: SyncArchive,
+) -> Result<SyncItemResult<SyncArchive>, ApiError> {
+    if !is_valid(&archive) {
+        return Ok(SyncItemResult::rejected(archive.id, "invalid_field"));
+    }
+    let Some((created_at, updated_at)) = parse_timestamps(&archive.created_at, &archive.updated_at) else {
+        return Ok(SyncItemResult::rejected(archive.id, "invalid_timestamp"));
+    };
+
+    let applied = sqlx::query!(
+        "INSERT INTO archives (id, user_id, name, description, created_at, updated_at) 
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (id) 
+         DO UPDATE SET 
+            name = EXCLUDED.name, 
+            description = EXCLUDED.description, 
+            updated_at = EXCLUDED.updated_at
+        WHERE archives.updated_at < EXCLUDED.updated_at
+          AND archives.user_id = EXCLUDED.user_id
+          AND archives.deleted_at IS NULL
+        RETURNING id",
+        archive.id,
+        user_id,
+        archive.name,
+        archive.description,
+        created_at.naive_utc(),
+        updated_at.naive_utc()
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .is_some();
+
+    let result = if applied {
+        SyncItemResult::applied(archive.id)
+    } else {
+        let current = sqlx::query_as!(
+            ArchiveRow,
+            "SELECT id, name, description, created_at, updated_at, deleted_at, revision
+             FROM archives WHERE id = $1 AND user_id = $2",
+            archive.id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        match current {
+            Some(row) if row.deleted_at.is_some() => SyncItemResult::rejected(archive.id, "deleted"),
+            Some(row) => SyncItemResult::stale(archive.id, row.into()),
+            None => SyncItemResult::rejected(archive.id, "id_conflict"),
+        }
+    };
+    Ok(result)
+}
+
+async fn push_tome(conn: &mut PgConnection, user_id: Uuid, tome: SyncTome) -> Result<SyncItemResult<SyncTome>, ApiError> {
+    if !is_valid(&tome) {
+        return Ok(SyncItemResult::rejected(tome.id, "invalid_field"));
+    }
+    let Some((created_at, updated_at)) = parse_timestamps(&tome.created_at, &tome.updated_at) else {
+        return Ok(SyncItemResult::rejected(tome.id, "invalid_timestamp"));
+    };
+
+    if !parent_exists(&mut *conn, ItemKind::Tome, user_id, &tome.archive_id).await? {
+        return Ok(SyncItemResult::rejected(tome.id, "missing_parent"));
+    }
+
+    // A tome may move to another archive; without an explicit position
+    // it keeps its place, or goes last in the archive it moved to
+    let applied = sqlx::query!(
+        "INSERT INTO tomes (id, archive_id, user_id, name, description, created_at, updated_at, position) 
+         SELECT $1, id, $3, $4, $5, $6, $7,
+                COALESCE($8, (SELECT MAX(position) + (MAX(position) < $9)::int FROM tomes WHERE archive_id = $2 AND deleted_at IS NULL), 0)
+         FROM archives WHERE id = $2 AND user_id = $3 AND deleted_at IS NULL
+         ON CONFLICT (id)
+         DO UPDATE SET 
+            archive_id = EXCLUDED.archive_id,
+            name = EXCLUDED.name, 
+            description = EXCLUDED.description, 
+            position = CASE WHEN $8 IS NULL AND tomes.archive_id = EXCLUDED.archive_id
+                            THEN tomes.position ELSE EXCLUDED.position END,
+            updated_at = EXCLUDED.updated_at
+         WHERE tomes.updated_at < EXCLUDED.updated_at
+           AND tomes.user_id = EXCLUDED.user_id
+           AND tomes.deleted_at IS NULL
+         RETURNING id",
+        tome.id,
+        tome.archive_id,
+        user_id,
+        tome.name,
+        tome.description,
+        created_at.naive_utc(),
+        updated_at.naive_utc(),
+        tome.position,
+        MAX_POSITION
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .is_some();
+
+    let result = if applied {
+        SyncItemResult::applied(tome.id)
+    } else {
+        let current = sqlx::query_as!(
+            TomeRow,
+            "SELECT id, archive_id, name, description, position, created_at, updated_at, deleted_at, revision
+             FROM tomes WHERE id = $1 AND user_id = $2",
+            tome.id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        match current {
+            Some(row) if row.deleted_at.is_some() => SyncItemResult::rejected(tome.id, "deleted"),
+            Some(row) => SyncItemResult::stale(tome.id, row.into()),
+            None => SyncItemResult::rejected(tome.id, "id_conflict"),
+        }
+    };
+    Ok(result)
+}
+
+async fn push_entry(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    entry: SyncEntry,
+) -> Result<SyncItemResult<SyncEntry>, ApiError> {
+    if !is_valid(&entry) {
+        return Ok(SyncItemResult::rejected(entry.id, "invalid_field"));
+    }
+    let Some((created_at, updated_at)) = parse_timestamps(&entry.created_at, &entry.updated_at) else {
+        return Ok(SyncItemResult::rejected(entry.id, "invalid_timestamp"));
+    };
+
+    if !parent_exists(&mut *conn, ItemKind::Entry, user_id, &entry.tome_id).await? {
+        return Ok(SyncItemResult::rejected(entry.id, "missing_parent"));
+    }
+
+    // Edits made against a known revision are merged with whatever the
+    // server has accepted since, rather than racing on timestamps
+    if let Some(base_revision) = entry.base_revision
+        && let Some(result) = merge_entry(&mut *conn, user_id, &entry, base_revision, updated_at).await?
+    {
+        return Ok(result);
+    }
+
+    let applied = sqlx::query!(
+        "INSERT INTO entries (id, tome_id, user_id, title, content, created_at, updated_at, position) 
+         SELECT $1, id, $3, $4, $5, $6, $7,
+                COALESCE($8, (SELECT MAX(position) + (MAX(position) < $9)::int FROM entries WHERE tome_id = $2 AND deleted_at IS NULL), 0)
+         FROM tomes WHERE id = $2 AND user_id = $3 AND deleted_at IS NULL
+         ON CONFLICT (id) 
+         DO UPDATE SET 
+            tome_id = EXCLUDED.tome_id,
+            title = EXCLUDED.title, 
+            content = EXCLUDED.content, 
+            position = CASE WHEN $8 IS NULL AND entries.tome_id = EXCLUDED.tome_id
+                            THEN entries.position ELSE EXCLUDED.position END,
+            updated_at = EXCLUDED.updated_at
+         WHERE entries.updated_at < EXCLUDED.updated_at
+           AND entries.user_id = EXCLUDED.user_id
+           AND entries.deleted_at IS NULL
+         RETURNING id",
+        entry.id,
+        entry.tome_id,
+        user_id,
+        entry.title,
+        entry.content,
+        created_at.naive_utc(),
+        updated_at.naive_utc(),
+        entry.position,
+        MAX_POSITION
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .is_some();
+
+    let result = if applied {
+        SyncItemResult::applied(entry.id)
+    } else {
+        let current = sqlx::query_as!(
+            EntryRow,
+            "SELECT id, tome_id, title, content, position, created_at, updated_at, deleted_at, revision
+             FROM entries WHERE id = $1 AND user_id = $2",
+            entry.id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        match current {
+            Some(row) if row.deleted_at.is_some() => SyncItemResult::rejected(entry.id, "deleted"),
+            Some(row) => SyncItemResult::stale(entry.id, row.into()),
+            None => SyncItemResult::rejected(entry.id, "id_conflict"),
+        }
+    };
+    Ok(result)
+}
+
+async fn push_deletion(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    kind: ItemKind,
+    deletion: SyncDeletion,
+) -> Result<SyncItemResult<()>, ApiError> {
+    let Some(deleted_at) = parse_deleted_at(&deletion) else {
+        return Ok(SyncItemResult::rejected(deletion.id, "invalid_timestamp"));
+    };
+
+    let deleted = match kind {
+        ItemKind::Archive => tombstones::delete_archive(conn, user_id, &deletion.id, deleted_at).await?,
+        ItemKind::Tome => tombstones::delete_tome(conn, user_id, &deletion.id, deleted_at).await?,
+        ItemKind::Entry => tombstones::delete_entry(conn, user_id, &deletion.id, deleted_at).await?,
+    };
+    Ok(SyncItemResult::deletion(deletion.id, deleted))
+}
+
+// Longest title a conflict copy keeps before the suffix is appended
+const CONFLICT_TITLE_MAX_CHARS: usize = 230;
+
+/// Applies a pushed entry edit that was made against `base_revision`.
+///
+/// The server's current text is merged with the pushed text using the
+/// snapshot at `base_revision` as the common ancestor. When the edits
+/// overlap, or the base is no longer known, the server keeps its version
+/// and the pushed text is saved as a new entry in the same tome so nothing
+/// is lost. Returns `None` when the entry does not exist yet and should be
+/// created normally.
+async fn merge_entry(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    entry: &SyncEntry,
+    base_revision: i64,
+    updated_at: DateTime<Utc>,
+) -> Result<Option<SyncItemResult<SyncEntry>>, ApiError> {
+    let current = sqlx::query_as!(
+        EntryRow,
+        "SELECT id, tome_id, title, content, position, created_at, updated_at, deleted_at, revision
+         FROM entries WHERE id = $1 AND user_id = $2
+         FOR UPDATE",
+        entry.id,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let current = match current {
+        Some(row) if row.deleted_at.is_some() => {
+            return Ok(Some(SyncItemResult::rejected(entry.id.clone(), "deleted")));
+        }
+        Some(row) => SyncEntry::from(row),
+        None => return Ok(None),
+    };
+    let current_revision = current.revision.unwrap_or_default();
+
+    // Nothing happened on the server since the client's base
+    if current_revision == base_revision {
+        apply_entry_edit(conn, user_id, entry, &entry.title, &entry.content, updated_at).await?;
+        return Ok(Some(SyncItemResult::applied(entry.id.clone())));
+    }
+
+    // Text as of the base revision; later rows may only have moved or
+    // touched the entry without changing it
+    let base = if base_revision < current_revision {
+        sqlx::query!(
+            "SELECT title, content FROM entry_revisions
+             WHERE entry_id = $1 AND user_id = $2 AND revision <= $3
+             ORDER BY revision DESC
+             LIMIT 1",
+            entry.id,
+            user_id,
+            base_revision
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+    } else {
+        None
+    };
+
+    let merged = base.and_then(|base| {
+        let title = merge3(&base.title, &current.title, &entry.title);
+        let content = merge3(&base.content, &current.content, &entry.content);
+        match (title, content) {
+            (MergeOutcome::Clean(title), MergeOutcome::Clean(content)) => Some((title, content)),
+            _ => None,
+        }
+    });
+
+    let moved = entry.tome_id != current.tome_id
+        || entry.position.is_some_and(|position| Some(position) != current.position);
+    match merged {
+        Some((title, content)) if title == current.title && content == current.content && !moved => {
+            Ok(Some(SyncItemResult::merged(entry.id.clone(), current)))
+        }
+        Some((title, content)) => {
+            let row = apply_entry_edit(conn, user_id, entry, &title, &content, updated_at).await?;
+            Ok(Some(SyncItemResult::merged(entry.id.clone(), row.into())))
+        }
+        None => {
+            let title: String = entry.title.chars().take(CONFLICT_TITLE_MAX_CHARS).collect();
+            let copy = sqlx::query_as!(
+                EntryRow,
+                "INSERT INTO entries (id, tome_id, user_id, title, content, created_at, updated_at, position)
+                 VALUES ($1, $2::text, $3, $4, $5, $6, $6,
+                         COALESCE((SELECT MAX(position) + (MAX(position) < $7)::int FROM entries WHERE tome_id = $2 AND deleted_at IS NULL), 0))
+                 RETURNING id, tome_id, title, content, position, created_at, updated_at, deleted_at, revision",
+                format!("entry-{}", Uuid::new_v4()),
+                current.tome_id,
+                user_id,
+                format!("{} (conflicted copy)", title),
+                entry.content,
+                updated_at.naive_utc(),
+                MAX_POSITION
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+
+            Ok(Some(SyncItemResult::conflict(entry.id.clone(), current, copy.into())))
+        }
+    }
+}
+
+/// Writes the given text to an existing entry, along with the placement
+/// pushed in `entry` (the same rules as for plain upserts apply).
+async fn apply_entry_edit(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    entry: &SyncEntry,
+    title: &str,
+    content: &str,
+    updated_at: DateTime<Utc>,
+) -> Result<EntryRow, ApiError> {
+    sqlx::query_as!(
+        EntryRow,
+        "UPDATE entries SET title = $3, content = $4, updated_at = GREATEST(updated_at, $5),
+            tome_id = $6::text,
+            position = CASE WHEN $7::int IS NULL AND tome_id = $6 THEN position
+                            ELSE COALESCE($7, (SELECT MAX(e.position) + (MAX(e.position) < $8)::int FROM entries e
+                                               WHERE e.tome_id = $6 AND e.deleted_at IS NULL), 0) END
+         WHERE id = $1 AND user_id = $2
+         RETURNING id, tome_id, title, content, position, created_at, updated_at, deleted_at, revision",
+        entry.id,
+        user_id,
+        title,
+        content,
+        updated_at.naive_utc(),
+        entry.tome_id,
+        entry.position,
+        MAX_POSITION
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(ApiError::from)
+}
+
+/// Whether `id` is a live row owned by the user that a pushed `kind` item
+/// may be placed under. Archives have no parent.
+async fn parent_exists(conn: &mut PgConnection, kind: ItemKind, user_id: Uuid, id: &str) -> Result<bool, ApiError> {
+    let parent = match kind {
+        ItemKind::Archive => return Ok(true),
+        ItemKind::Tome => sqlx::query_scalar!(
+            "SELECT id FROM archives WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+            id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+        ItemKind::Entry => sqlx::query_scalar!(
+            "SELECT id FROM tomes WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+            id,
+            user_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?,
+    };
+    Ok(parent.is_some())
+}
+
+fn is_valid(item: &impl Validate) -> bool {
+    let mut errors = FieldErrors::default();
+    item.validate(&mut errors);
+    errors.is_empty()
+}
+
+fn parse_timestamps(created_at: &str, updated_at: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    Some((created_at.parse().ok()?, updated_at.parse().ok()?))
+}
+
+fn parse_deleted_at(deletion: &SyncDeletion) -> Option<DateTime<Utc>> {
+    deletion.deleted_at.parse().ok()
+}
+
+/// A tombstone for a row deleted at `deleted_at`.
+fn deletion(id: String, deleted_at: NaiveDateTime) -> SyncDeletion {
+    SyncDeletion { id, deleted_at: deleted_at.and_utc().to_rfc3339() }
+}
+
+/// Rows as read for sync, tombstones included.
+struct ArchiveRow {
+    id: String,
+    name: String,
+    description: Option<String>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    deleted_at: Option<NaiveDateTime>,
+    revision: i64,
+}
+
+struct TomeRow {
+    id: String,
+    archive_id: String,
+    name: String,
+    description: Option<String>,
+    position: i32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    deleted_at: Option<NaiveDateTime>,
+    revision: i64,
+}
+
+struct EntryRow {
+    id: String,
+    tome_id: String,
+    title: String,
+    content: String,
+    position: i32,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    deleted_at: Option<NaiveDateTime>,
+    revision: i64,
+}
+
+impl From<ArchiveRow> for SyncArchive {
+    fn from(row: ArchiveRow) -> Self {
+        SyncArchive {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            created_at: row.created_at.and_utc().to_rfc3339(),
+            updated_at: row.updated_at.and_utc().to_rfc3339(),
+        }
+    }
+}
+
+impl From<TomeRow> for SyncTome {
+    fn from(row: TomeRow) -> Self {
+        SyncTome {
+            id: row.id,
+            archive_id: row.archive_id,
+            name: row.name,
+            description: row.description,
+            created_at: row.created_at.and_utc().to_rfc3339(),
+            updated_at: row.updated_at.and_utc().to_rfc3339(),
+            position: Some(row.position),
+        }
+    }
+}
+
+impl From<EntryRow> for SyncEntry {
+    fn from(row: EntryRow) -> Self {
+        SyncEntry {
+            id: row.id,
+            tome_id: row.tome_id,
+            title: row.title,
+            content: row.content,
+            created_at: row.created_at.and_utc().to_rfc3339(),
+            updated_at: row.updated_at.and_utc().to_rfc3339(),
+            position: Some(row.position),
+            revision: Some(row.revision),
+            base_revision: None,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Wire protocol versions for `/sync`.
+//!
+//! * v1 is what the original desktop clients speak: tomes carry their name
+//!   as `title`. It is the default on `/api/v1/sync`.
+//! * v2 uses `name` everywhere, matching the REST API. It is the default on
+//!   `/api/v2/sync`.
+//!
+//! A client can pick a version explicitly with the `X-Sync-Protocol` header
+//! on either path, and the response says which version it was written in.
+//! The sync service only ever sees the v2 shape; all translation happens
+//! here.
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderName},
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::ApiError;
+
+pub const SYNC_PROTOCOL_HEADER: HeaderName = HeaderName::from_static("x-sync-protocol");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncProtocol {
+    V1,
+    V2,
+}
+
+impl SyncProtocol {
+    pub const LATEST: Self = Self::V2;
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "1" => Some(Self::V1),
+            "2" => Some(Self::V2),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1 => "1",
+            Self::V2 => "2",
+        }
+    }
+
+    /// Rewrites a push body sent in this version into the current shape.
+    pub fn upgrade_request(&self, mut body: Value) -> Value {
+        if *self == Self::V1 {
+            for tome in items_mut(body.get_mut("tomes")) {
+                // Accept `name` too, from v1 clients that already moved on
+                if let Some(title) = tome.as_object_mut().and_then(|tome| tome.remove("title"))
+                    && tome.get("name").is_none()
+                {
+                    tome["name"] = title;
+                }
+            }
+        }
+        body
+    }
+
+    /// Serializes a response in this version.
+    pub fn encode<T: Serialize>(&self, body: &T) -> Result<Value, serde_json::Error> {
+        let mut body = serde_json::to_value(body)?;
+        if *self == Self::V1 {
+            // Pull responses list tomes at the top level; push responses
+            // nest the server's copy inside each result
+            for tome in items_mut(body.get_mut("tomes")) {
+                add_legacy_title(tome);
+            }
+            for result in items_mut(body.get_mut("results").and_then(|results| results.get_mut("tomes"))) {
+                if let Some(current) = result.get_mut("current") {
+                    add_legacy_title(current);
+                }
+            }
+        }
+        Ok(body)
+    }
+}
+
+/// Picks the protocol from `X-Sync-Protocol`, falling back to the default
+/// installed on the router as an extension (or the latest version).
+#[async_trait]
+impl<S> FromRequestParts<S> for SyncProtocol
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(SYNC_PROTOCOL_HEADER) {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(Self::parse)
+                .ok_or_else(|| ApiError::bad_request("Unsupported sync protocol version")),
+            None => Ok(parts.extensions.get::<Self>().copied().unwrap_or(Self::LATEST)),
+        }
+    }
+}
+
+fn items_mut(value: Option<&mut Value>) -> impl Iterator<Item = &mut Value> {
+    value.and_then(Value::as_array_mut).into_iter().flatten()
+}
+
+// v1 clients read the tome name from `title`; keep `name` alongside it for
+// clients that were already reading the v2 field from /api/v1
+fn add_legacy_title(tome: &mut Value) {
+    if let Some(name) = tome.get("name").cloned() {
+        tome["title"] = name;
+    }
+}
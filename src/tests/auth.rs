@@ -0,0 +1,142 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::TestApp;
+use crate::auth::AuthKeys;
+
+async fn login(app: &TestApp, username: &str, password: &str) -> (StatusCode, serde_json::Value) {
+    app.request(
+        Method::POST,
+        "/api/v1/auth/login",
+        None,
+        Some(json!({ "username": username, "password": password })),
+    )
+    .await
+}
+
+#[sqlx::test]
+async fn registered_users_log_in_and_call_the_api(pool: PgPool) {
+    let app = TestApp::new(pool);
+    app.register("alice").await;
+
+    let (status, session) = login(&app, "alice", "correct horse battery").await;
+    assert_eq!(status, StatusCode::OK, "{}", session);
+    assert_eq!(session["token_type"], "Bearer");
+    assert_eq!(session["username"], "alice");
+
+    // The email address works as the login name too
+    let (status, _) = login(&app, "alice@example.com", "correct horse battery").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let token = session["token"].as_str().unwrap();
+    let (status, me) = app.request(Method::GET, "/api/v1/users/me", Some(token), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", me);
+    assert_eq!(me["id"], session["user_id"]);
+}
+
+#[sqlx::test]
+async fn wrong_passwords_and_unknown_users_are_rejected(pool: PgPool) {
+    let app = TestApp::new(pool);
+    app.register("alice").await;
+
+    let (status, body) = login(&app, "alice", "incorrect horse battery").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "unauthorized");
+    assert!(body.get("token").is_none());
+
+    // Indistinguishable from a wrong password
+    let (status, _) = login(&app, "mallory", "correct horse battery").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn expired_and_tampered_tokens_are_rejected(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let (status, me) = app.request(Method::GET, "/api/v1/users/me", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let user_id: Uuid = me["id"].as_str().unwrap().parse().unwrap();
+
+    // Expired well beyond the validation leeway, signed with the right key
+    let (expired, _) = AuthKeys::new(b"test-secret", chrono::Duration::minutes(-10))
+        .issue_token(user_id)
+        .unwrap();
+    // Valid claims, signed with someone else's key
+    let (forged, _) = AuthKeys::new(b"other-secret", chrono::Duration::minutes(5))
+        .issue_token(user_id)
+        .unwrap();
+    // The real token with its signature altered
+    let (unsigned, signature) = token.rsplit_once('.').unwrap();
+    let flipped = if signature.starts_with('A') { 'B' } else { 'A' };
+    let tampered = format!("{}.{}{}", unsigned, flipped, &signature[1..]);
+
+    for bad in [expired.as_str(), forged.as_str(), tampered.as_str(), "not-a-token"] {
+        let (status, body) = app.request(Method::GET, "/api/v1/users/me", Some(bad), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "accepted {}", bad);
+        assert_eq!(body["code"], "unauthorized");
+    }
+}
+
+#[sqlx::test]
+async fn deactivated_accounts_lose_access(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+
+    sqlx::query("UPDATE users SET is_active = false WHERE username = 'alice'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (status, body) = app.request(Method::GET, "/api/v1/archives", Some(&token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+    let (status, _) = login(&app, "alice", "correct horse battery").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn deleted_accounts_lose_access(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let (_, me) = app.request(Method::GET, "/api/v1/users/me", Some(&token), None).await;
+
+    let (status, _) = app
+        .request(Method::DELETE, &format!("/api/v1/users/{}", me["id"].as_str().unwrap()), Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = app.request(Method::GET, "/api/v1/users/me", Some(&token), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+}
+
+#[sqlx::test]
+async fn logins_match_at_most_one_account(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    app.register("alice").await;
+
+    // An account from before usernames were checked, named like alice's email
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password_hash, is_active)
+         VALUES ($1, 'alice@example.com', 'other@example.com', 'not a hash', true)",
+    )
+    .bind(Uuid::new_v4())
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let (status, session) = login(&app, "alice@example.com", "correct horse battery").await;
+    assert_eq!(status, StatusCode::OK, "{}", session);
+    assert_eq!(session["username"], "alice");
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/auth/register",
+            None,
+            Some(json!({ "username": "bob@example.com", "email": "bob@example.com", "password": "correct horse battery" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["details"]["fields"][0]["field"], "username");
+}
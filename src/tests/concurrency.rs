@@ -0,0 +1,152 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+/// Sends a request and returns the status, `ETag` and decoded body.
+async fn send(
+    app: &TestApp,
+    method: Method,
+    uri: &str,
+    token: &str,
+    if_match: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Option<String>, Value) {
+    let headers: Vec<(&str, &str)> = if_match.map(|tag| ("if-match", tag)).into_iter().collect();
+    let response = app.raw_request(method, uri, Some(token), &headers, body).await;
+    let status = response.status();
+    let etag = response
+        .headers()
+        .get("etag")
+        .map(|value| value.to_str().unwrap().to_string());
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, etag, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[sqlx::test]
+async fn stale_if_match_is_rejected(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (status, created_tag, archive) = send(
+        &app,
+        Method::POST,
+        "/api/v1/archives",
+        &token,
+        None,
+        Some(json!({ "name": "draft", "description": "notes" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/v1/archives/{}", archive["id"].as_str().unwrap());
+
+    let (_, fetched_tag, _) = send(&app, Method::GET, &uri, &token, None, None).await;
+    let first_tag = fetched_tag.unwrap();
+    assert_eq!(created_tag.as_deref(), Some(first_tag.as_str()));
+
+    // The first tab saves against the version it loaded
+    let (status, second_tag, body) = send(
+        &app,
+        Method::PATCH,
+        &uri,
+        &token,
+        Some(&first_tag),
+        Some(json!({ "name": "final" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["name"], "final");
+    assert_eq!(body["description"], "notes");
+    let second_tag = second_tag.unwrap();
+    assert_ne!(second_tag, first_tag);
+
+    // The second tab still holds the old version
+    for method in [Method::PATCH, Method::PUT] {
+        let (status, _, body) = send(
+            &app,
+            method,
+            &uri,
+            &token,
+            Some(&first_tag),
+            Some(json!({ "name": "clobbered", "description": null })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(body["code"], "precondition_failed");
+    }
+
+    let (status, _, body) = send(
+        &app,
+        Method::PATCH,
+        &uri,
+        &token,
+        Some(&format!("W/{}, \"0\"", second_tag)),
+        Some(json!({ "description": null })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["name"], "final");
+    assert_eq!(body["description"], Value::Null);
+
+    // Unconditional writes still go through
+    let (status, _, _) = send(&app, Method::PATCH, &uri, &token, None, Some(json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = send(&app, Method::PATCH, &uri, &token, Some("*"), Some(json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, _) = send(
+        &app,
+        Method::PATCH,
+        "/api/v1/archives/archive-missing",
+        &token,
+        Some(&second_tag),
+        Some(json!({ "name": "x" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn tomes_patch_only_supplied_fields(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (_, _, archive) = send(
+        &app,
+        Method::POST,
+        "/api/v1/archives",
+        &token,
+        None,
+        Some(json!({ "name": "archive" })),
+    )
+    .await;
+    let (status, tag, tome) = send(
+        &app,
+        Method::POST,
+        &format!("/api/v1/archives/{}/tomes", archive["id"].as_str().unwrap()),
+        &token,
+        None,
+        Some(json!({ "name": "tome", "description": "kept" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/v1/tomes/{}", tome["id"].as_str().unwrap());
+
+    let (status, _, body) = send(
+        &app,
+        Method::PATCH,
+        &uri,
+        &token,
+        tag.as_deref(),
+        Some(json!({ "name": "renamed" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["name"], "renamed");
+    assert_eq!(body["description"], "kept");
+
+    let (status, _, body) = send(&app, Method::PATCH, &uri, &token, None, Some(json!({ "name": "" }))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+}
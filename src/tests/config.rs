@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::TestApp;
+use crate::config::{Config, Features, LogFormat};
+
+fn load(toml: Option<&str>, vars: &[(&str, &str)]) -> Result<Config, Vec<String>> {
+    let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    Config::from_sources(toml, |name| vars.get(name).cloned()).map_err(|e| e.0)
+}
+
+const REQUIRED: &[(&str, &str)] = &[("DATABASE_URL", "postgres://localhost/scribe"), ("JWT_SECRET", "secret")];
+
+#[test]
+fn defaults_apply_when_only_required_settings_are_given() {
+    let config = load(None, REQUIRED).unwrap();
+    assert_eq!(config.server.bind.to_string(), "0.0.0.0:8080");
+    assert_eq!(config.database.max_connections, 5);
+    assert_eq!(config.log.format, LogFormat::Text);
+    assert!(config.cors.allowed_origins.is_empty());
+    assert!(config.features.registration);
+}
+
+#[test]
+fn environment_overrides_the_config_file() {
+    let toml = r#"
+        [server]
+        bind = "127.0.0.1:9000"
+        max_body_bytes = 1024
+
+        [database]
+        url = "postgres://file/scribe"
+        max_connections = 20
+
+        [auth]
+        jwt_secret = "from-file"
+
+        [cors]
+        allowed_origins = ["https://app.example.com"]
+
+        [features]
+        registration = false
+    "#;
+    let config = load(
+        Some(toml),
+        &[
+            ("DATABASE_MAX_CONNECTIONS", "8"),
+            ("LOG_FORMAT", "json"),
+            ("CORS_ALLOWED_ORIGINS", "https://a.example.com, http://localhost:3000"),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(config.server.bind.to_string(), "127.0.0.1:9000");
+    assert_eq!(config.server.max_body_bytes, 1024);
+    assert_eq!(config.database.url, "postgres://file/scribe");
+    assert_eq!(config.database.max_connections, 8);
+    assert_eq!(config.auth.jwt_secret, "from-file");
+    assert_eq!(config.log.format, LogFormat::Json);
+    assert_eq!(config.cors.allowed_origins, ["https://a.example.com", "http://localhost:3000"]);
+    assert!(!config.features.registration);
+}
+
+#[test]
+fn every_problem_is_reported_at_once() {
+    let Err(problems) = load(
+        None,
+        &[
+            ("DATABASE_MAX_CONNECTIONS", "many"),
+            ("DATABASE_MIN_CONNECTIONS", "10"),
+            ("LOG_FORMAT", "xml"),
+            ("CORS_ALLOWED_ORIGINS", "https://ok.example.com,https://slash.example.com/,app.example.com"),
+        ],
+    ) else {
+        panic!("invalid settings were accepted");
+    };
+
+    let expected = [
+        "DATABASE_MAX_CONNECTIONS",
+        "LOG_FORMAT",
+        "database.url",
+        "database.min_connections",
+        "auth.jwt_secret",
+        "https://slash.example.com/",
+        "`app.example.com`",
+    ];
+    assert_eq!(problems.len(), expected.len(), "{:?}", problems);
+    for (problem, expected) in problems.iter().zip(expected) {
+        assert!(problem.contains(expected), "{:?} should mention {}", problem, expected);
+    }
+}
+
+#[test]
+fn unknown_keys_in_the_config_file_are_rejected() {
+    let Err(problems) = load(Some("[server]\nport = 8080\n"), REQUIRED) else {
+        panic!("an unknown key was accepted");
+    };
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("port"), "{:?}", problems);
+}
+
+#[sqlx::test]
+async fn registration_can_be_switched_off(pool: PgPool) {
+    // Accounts that existed before registration was closed
+    let open = TestApp::new(pool.clone());
+    let alice = open.register("alice").await;
+    let admin = open.register("admin").await;
+    sqlx::query("UPDATE users SET is_admin = true WHERE username = 'admin'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = TestApp::with_features(pool, Features { registration: false, ..Features::default() });
+    let account = |username: &str| {
+        json!({
+            "username": username,
+            "email": format!("{}@example.com", username),
+            "password": "correct horse battery",
+        })
+    };
+
+    let (status, body) = app
+        .request(Method::POST, "/api/v1/auth/register", None, Some(account("bob")))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    // Signed-in users cannot open accounts for others either
+    let (status, body) = app
+        .request(Method::POST, "/api/v1/users", Some(&alice), Some(account("carol")))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .request(Method::POST, "/api/v1/users", Some(&admin), Some(account("dave")))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[test]
+fn the_example_config_file_is_valid() {
+    let config = load(Some(include_str!("../../config.example.toml")), &[("JWT_SECRET", "secret")]).unwrap();
+    assert_eq!(config.database.url, "postgres://localhost/scribe");
+}
+
+#[test]
+fn rate_limit_budgets_must_allow_a_request() {
+    let toml = r#"
+        [rate_limit.sync]
+        burst = 0
+        per_minute = 10
+    "#;
+    let Err(errors) = load(Some(toml), REQUIRED) else {
+        panic!("a zero burst was accepted");
+    };
+    assert_eq!(errors, ["rate_limit.sync: burst and per_minute must be at least 1"]);
+}
+
+#[test]
+fn durations_must_fit_in_a_timestamp() {
+    let vars = [
+        ("DATABASE_URL", "postgres://localhost/scribe"),
+        ("JWT_SECRET", "secret"),
+        ("JWT_TTL_SECONDS", "9223372036854775807"),
+        ("TOMBSTONE_RETENTION_DAYS", "9223372036854775807"),
+    ];
+    let Err(errors) = load(None, &vars) else {
+        panic!("durations too large for chrono were accepted");
+    };
+    assert_eq!(
+        errors,
+        [
+            "auth.token_ttl_seconds must be between 1 and 31536000",
+            "sync.tombstone_retention_days must be between 1 and 36500",
+        ]
+    );
+
+    let vars = [
+        ("DATABASE_URL", "postgres://localhost/scribe"),
+        ("JWT_SECRET", "secret"),
+        ("JWT_TTL_SECONDS", "31536000"),
+        ("TOMBSTONE_RETENTION_DAYS", "36500"),
+    ];
+    let config = load(None, &vars).unwrap();
+    let now = chrono::Utc::now();
+    assert!(now.checked_add_signed(chrono::Duration::seconds(config.auth.token_ttl_seconds)).is_some());
+    assert!(now.checked_sub_signed(chrono::Duration::days(config.sync.tombstone_retention_days)).is_some());
+}
@@ -0,0 +1,151 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+/// Creates an archive, a tome and an entry, returning the entry.
+async fn create_entry(app: &TestApp, token: &str, content: &str) -> Value {
+    let archive_id = app.create_archive(token, "archive").await;
+    let (_, tome) = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/archives/{}/tomes", archive_id),
+            Some(token),
+            Some(json!({ "name": "tome" })),
+        )
+        .await;
+    let (status, entry) = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/tomes/{}/entries", tome["id"].as_str().unwrap()),
+            Some(token),
+            Some(json!({ "title": "notes", "content": content })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", entry);
+    entry
+}
+
+#[sqlx::test]
+async fn edits_are_kept_diffed_and_restorable(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let entry = create_entry(&app, &token, "one\ntwo\n").await;
+    let entry_uri = format!("/api/v1/entries/{}", entry["id"].as_str().unwrap());
+
+    let (_, edited) = app
+        .request(Method::PATCH, &entry_uri, Some(&token), Some(json!({ "content": "one\n2\n" })))
+        .await;
+    // Renames alone are recorded too; touching nothing is not
+    app.request(Method::PATCH, &entry_uri, Some(&token), Some(json!({ "title": "renamed" })))
+        .await;
+    app.request(Method::PATCH, &entry_uri, Some(&token), Some(json!({}))).await;
+
+    let (status, revisions) = app
+        .request(Method::GET, &format!("{}/revisions", entry_uri), Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", revisions);
+    let revisions = revisions.as_array().unwrap();
+    assert_eq!(revisions.len(), 3);
+    assert_eq!(revisions[0]["title"], "renamed");
+    assert_eq!(revisions[2]["revision"], entry["revision"]);
+
+    let (status, diff) = app
+        .request(
+            Method::GET,
+            &format!("{}/revisions/{}/diff", entry_uri, edited["revision"]),
+            Some(&token),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", diff);
+    assert_eq!(diff["from"], entry["revision"]);
+    assert!(diff["diff"].as_str().unwrap().contains("-two\n+2\n"), "{}", diff);
+
+    let (status, restored) = app
+        .request(
+            Method::POST,
+            &format!("{}/revisions/{}/restore", entry_uri, entry["revision"]),
+            Some(&token),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", restored);
+    assert_eq!(restored["title"], "notes");
+    assert_eq!(restored["content"], "one\ntwo\n");
+
+    let (_, revisions) = app
+        .request(Method::GET, &format!("{}/revisions", entry_uri), Some(&token), None)
+        .await;
+    assert_eq!(revisions.as_array().unwrap().len(), 4);
+}
+
+#[sqlx::test]
+async fn revisions_are_private(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let entry = create_entry(&app, &alice, "secret").await;
+    let uri = format!("/api/v1/entries/{}/revisions/{}", entry["id"].as_str().unwrap(), entry["revision"]);
+
+    let (status, _) = app.request(Method::GET, &uri, Some(&bob), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app
+        .request(Method::POST, &format!("{}/restore", uri), Some(&bob), None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn single_revisions_and_explicit_diffs(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let entry = create_entry(&app, &token, "one\n").await;
+    let entry_uri = format!("/api/v1/entries/{}", entry["id"].as_str().unwrap());
+    let first = &entry["revision"];
+
+    app.request(Method::PATCH, &entry_uri, Some(&token), Some(json!({ "content": "one\ntwo\n" })))
+        .await;
+    let (_, latest) = app
+        .request(Method::PATCH, &entry_uri, Some(&token), Some(json!({ "content": "one\ntwo\nthree\n" })))
+        .await;
+
+    let (status, revision) = app
+        .request(Method::GET, &format!("{}/revisions/{}", entry_uri, first), Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", revision);
+    assert_eq!(revision["content"], "one\n");
+    assert_eq!(revision["title"], "notes");
+
+    // The first revision is compared with an empty entry
+    let (_, diff) = app
+        .request(Method::GET, &format!("{}/revisions/{}/diff", entry_uri, first), Some(&token), None)
+        .await;
+    assert_eq!(diff["from"], 0);
+    assert!(diff["diff"].as_str().unwrap().contains("+one\n"), "{}", diff);
+
+    // `from` skips over the revisions in between
+    let (status, diff) = app
+        .request(
+            Method::GET,
+            &format!("{}/revisions/{}/diff?from={}", entry_uri, latest["revision"], first),
+            Some(&token),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", diff);
+    assert_eq!(diff["from"], *first);
+    assert!(diff["diff"].as_str().unwrap().contains("+two\n+three\n"), "{}", diff);
+
+    let missing = format!("{}/revisions/999999", entry_uri);
+    for (method, uri) in [
+        (Method::GET, missing.clone()),
+        (Method::GET, format!("{}/diff", missing)),
+        (Method::GET, format!("{}/revisions/{}/diff?from=999999", entry_uri, first)),
+        (Method::POST, format!("{}/restore", missing)),
+    ] {
+        let (status, body) = app.request(method, &uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}: {}", uri, body);
+    }
+}
@@ -0,0 +1,108 @@
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+use super::TestApp;
+
+async fn error_body(response: axum::response::Response) -> Value {
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[sqlx::test]
+async fn errors_share_one_json_shape(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let (status, body) = app.request(Method::GET, "/no/such/route", None, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["details"], json!({ "resource": "route" }));
+    assert!(body["message"].is_string());
+    assert!(body["request_id"].is_string());
+
+    let (status, body) = app.request(Method::GET, "/api/v1/archives", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "unauthorized");
+    assert_eq!(body["details"], Value::Null);
+}
+
+#[sqlx::test]
+async fn errors_carry_the_callers_request_id(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app
+        .raw_request(Method::GET, "/api/v1/archives", None, &[("x-request-id", "trace-me-123")], None)
+        .await;
+    assert_eq!(error_body(response).await["request_id"], "trace-me-123");
+}
+
+#[sqlx::test]
+async fn client_errors_use_specific_codes(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/auth/register",
+            None,
+            Some(json!({
+                "username": "alice",
+                "email": "someone-else@example.com",
+                "password": "correct horse battery",
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "conflict");
+
+    let (status, body) = app
+        .request(Method::GET, "/api/v1/archives/archive-missing", Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["details"], json!({ "resource": "archive" }));
+
+    let (status, body) = app
+        .request(Method::GET, "/api/v1/sync?cursor=garbage", Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "bad_request");
+}
+
+#[sqlx::test]
+async fn rejected_requests_answer_in_json(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    // Bodies that are not JSON at all, on a plain and on the sync endpoint
+    for uri in ["/api/v1/auth/login", "/api/v1/sync"] {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from("{\"username\": "))
+            .unwrap();
+        let response = app.app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        let body = error_body(response).await;
+        assert_eq!(body["code"], "invalid_body", "{}", uri);
+        assert!(body["request_id"].is_string());
+    }
+
+    let (status, body) = app
+        .request(Method::GET, "/api/v1/users/not-a-uuid", Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_path");
+    assert!(body["message"].is_string());
+
+    let (status, body) = app
+        .request(Method::GET, "/api/v1/archives?limit=lots", Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_query");
+    assert!(body["request_id"].is_string());
+}
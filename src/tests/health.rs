@@ -0,0 +1,44 @@
+use axum::http::{Method, StatusCode};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+#[sqlx::test]
+async fn liveness_needs_no_authentication(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let (status, body) = app.request(Method::GET, "/healthz", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+}
+
+#[sqlx::test]
+async fn readiness_reports_pending_migrations(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+
+    let (status, body) = app.request(Method::GET, "/readyz", None, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["checks"]["database"], "ok");
+    assert_eq!(body["checks"]["migrations"], "ok");
+
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (status, body) = app.request(Method::GET, "/readyz", None, None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{}", body);
+    assert_eq!(body["status"], "unavailable");
+    assert_eq!(body["checks"]["migrations"], "1 pending");
+}
+
+#[sqlx::test]
+async fn readiness_keeps_database_errors_out_of_the_response(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    pool.close().await;
+
+    let (status, body) = app.request(Method::GET, "/readyz", None, None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{}", body);
+    assert_eq!(body["checks"]["database"], "unavailable");
+    assert_eq!(body["checks"]["migrations"], "unknown");
+}
@@ -0,0 +1,113 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::TestApp;
+
+#[sqlx::test]
+async fn tomes_are_created_and_listed_under_their_archive(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let archive_id = app.create_archive(&token, "archive").await;
+    let other_archive_id = app.create_archive(&token, "archive").await;
+    let tomes_uri = format!("/api/v1/archives/{}/tomes", archive_id);
+
+    let (status, body) = app
+        .request(Method::POST, &tomes_uri, Some(&token), Some(json!({ "name": "generated id" })))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["archive_id"], archive_id);
+    assert!(body["id"].as_str().unwrap().starts_with("tome-"));
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            &tomes_uri,
+            Some(&token),
+            Some(json!({ "id": "tome-chosen", "name": "chosen id", "description": "notes" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["id"], "tome-chosen");
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/archives/{}/tomes", other_archive_id),
+            Some(&token),
+            Some(json!({ "name": "elsewhere" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .request(Method::GET, &format!("{}?sort=name", tomes_uri), Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let names: Vec<_> = body.as_array().unwrap().iter().map(|tome| tome["name"].clone()).collect();
+    assert_eq!(names, vec![json!("chosen id"), json!("generated id")]);
+}
+
+#[sqlx::test]
+async fn nested_routes_require_an_owned_parent(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let archive_id = app.create_archive(&alice, "archive").await;
+    let tomes_uri = format!("/api/v1/archives/{}/tomes", archive_id);
+
+    let (status, _) = app.request(Method::GET, &tomes_uri, Some(&bob), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = app
+        .request(Method::POST, &tomes_uri, Some(&bob), Some(json!({ "name": "intruder" })))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["details"]["resource"], "archive");
+
+    for uri in [
+        "/api/v1/archives/archive-missing/tomes".to_string(),
+        format!("/api/v1/archives/{}/tree", archive_id),
+        "/api/v1/tomes/tome-missing/entries".to_string(),
+    ] {
+        let (status, _) = app.request(Method::GET, &uri, Some(&bob), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+    }
+}
+
+#[sqlx::test]
+async fn archive_tree_lists_entries_in_sidebar_order(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let archive_id = app.create_archive(&token, "archive").await;
+
+    let (_, tome) = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/archives/{}/tomes", archive_id),
+            Some(&token),
+            Some(json!({ "name": "tome" })),
+        )
+        .await;
+    let entries_uri = format!("/api/v1/tomes/{}/entries", tome["id"].as_str().unwrap());
+    for title in ["first", "second"] {
+        let (status, body) = app
+            .request(Method::POST, &entries_uri, Some(&token), Some(json!({ "title": title, "content": "text" })))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    let (status, body) = app.request(Method::GET, &entries_uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().unwrap().len(), 2);
+
+    let (status, tree) = app
+        .request(Method::GET, &format!("/api/v1/archives/{}/tree", archive_id), Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", tree);
+    assert_eq!(tree["id"], archive_id);
+    let entries = tree["tomes"][0]["entries"].as_array().unwrap();
+    let titles: Vec<_> = entries.iter().map(|entry| entry["title"].clone()).collect();
+    assert_eq!(titles, vec![json!("first"), json!("second")]);
+    assert!(entries[0].get("content").is_none());
+}
@@ -0,0 +1,91 @@
+use axum::http::{header, Method, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::TestApp;
+use crate::config::Features;
+
+async fn scrape(app: &TestApp) -> String {
+    let response = app.raw_request(Method::GET, "/metrics", None, &[], None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+fn assert_has(metrics: &str, line: &str) {
+    assert!(metrics.lines().any(|l| l == line), "missing `{}` in:\n{}", line, metrics);
+}
+
+#[sqlx::test]
+async fn sync_traffic_is_counted(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let archive = |name: &str, updated_at: &str| {
+        json!({
+            "id": "archive-1",
+            "name": name,
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": updated_at,
+        })
+    };
+    for (name, updated_at) in [("first", "2025-06-01T00:00:00Z"), ("older", "2025-03-01T00:00:00Z")] {
+        let (status, _) = app
+            .request(
+                Method::POST,
+                "/api/v1/sync",
+                Some(&token),
+                Some(json!({ "archives": [archive(name, updated_at)], "tomes": [], "entries": [] })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, _) = app.request(Method::GET, "/api/v1/sync", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let metrics = scrape(&app).await;
+    assert_has(
+        &metrics,
+        r#"stackscribe_sync_pushed_rows_total{change="upsert",kind="archive",status="applied"} 1"#,
+    );
+    assert_has(
+        &metrics,
+        r#"stackscribe_sync_pushed_rows_total{change="upsert",kind="archive",status="stale"} 1"#,
+    );
+    assert_has(&metrics, r#"stackscribe_sync_stale_rows_total{kind="archive"} 1"#);
+    assert_has(&metrics, r#"stackscribe_sync_pulled_rows_total{change="upsert",kind="archive"} 1"#);
+    assert_has(&metrics, r#"stackscribe_sync_payload_bytes_count{direction="pull"} 1"#);
+    assert_has(&metrics, r#"stackscribe_sync_payload_bytes_count{direction="push"} 2"#);
+}
+
+#[sqlx::test]
+async fn requests_and_pool_usage_are_reported(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    for _ in 0..2 {
+        let (status, _) = app.request(Method::GET, "/api/v1/archives/missing", Some(&token), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    let metrics = scrape(&app).await;
+    assert_has(
+        &metrics,
+        r#"stackscribe_http_requests_total{method="GET",route="/api/v1/archives/:id",status="404"} 2"#,
+    );
+    assert_has(
+        &metrics,
+        r#"stackscribe_http_request_duration_seconds_count{method="GET",route="/api/v1/archives/:id"} 2"#,
+    );
+    assert!(metrics.contains(r#"stackscribe_db_pool_connections{state="in_use"}"#), "{}", metrics);
+    assert!(metrics.contains("stackscribe_db_pool_max_connections"), "{}", metrics);
+}
+
+#[sqlx::test]
+async fn metrics_can_be_switched_off(pool: PgPool) {
+    let app = TestApp::with_features(pool, Features { metrics: false, ..Features::default() });
+
+    let (status, _) = app.request(Method::GET, "/metrics", None, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,119 @@
+use axum::http::{header, Method, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::TestApp;
+use crate::config::Config;
+
+fn with_origins(origins: &[&str]) -> Config {
+    let mut config = Config::default();
+    config.cors.allowed_origins = origins.iter().map(|origin| origin.to_string()).collect();
+    config
+}
+
+#[sqlx::test]
+async fn cors_allows_only_listed_origins(pool: PgPool) {
+    let app = TestApp::with_config(pool, with_origins(&["https://app.example.com"]));
+
+    let preflight = app
+        .raw_request(
+            Method::OPTIONS,
+            "/api/v1/archives",
+            None,
+            &[
+                ("origin", "https://app.example.com"),
+                ("access-control-request-method", "POST"),
+                ("access-control-request-headers", "authorization,content-type"),
+            ],
+            None,
+        )
+        .await;
+    assert_eq!(preflight.status(), StatusCode::OK);
+    assert_eq!(
+        preflight.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://app.example.com"
+    );
+
+    let response = app
+        .raw_request(Method::GET, "/healthz", None, &[("origin", "https://evil.example.com")], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.headers().contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[sqlx::test]
+async fn cors_is_off_without_origins(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app
+        .raw_request(Method::GET, "/healthz", None, &[("origin", "https://app.example.com")], None)
+        .await;
+    assert!(!response.headers().contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[sqlx::test]
+async fn responses_are_compressed_when_accepted(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+
+    let response = app
+        .raw_request(Method::GET, "/readyz", None, &[("accept-encoding", "gzip")], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+    let response = app.raw_request(Method::GET, "/readyz", None, &[], None).await;
+    assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+
+    let mut config = Config::default();
+    config.server.compression = false;
+    let app = TestApp::with_config(pool, config);
+    let response = app
+        .raw_request(Method::GET, "/readyz", None, &[("accept-encoding", "gzip, br")], None)
+        .await;
+    assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+}
+
+#[sqlx::test]
+async fn oversized_bodies_are_rejected(pool: PgPool) {
+    let mut config = Config::default();
+    config.server.max_body_bytes = 256;
+    let app = TestApp::with_config(pool, config);
+    let token = app.register("alice").await;
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/archives",
+            Some(&token),
+            Some(json!({ "name": "Small", "description": "x".repeat(100) })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/archives",
+            Some(&token),
+            Some(json!({ "name": "Large", "description": "x".repeat(1000) })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[sqlx::test]
+async fn request_ids_are_echoed_or_generated(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app
+        .raw_request(Method::GET, "/healthz", None, &[("x-request-id", "client-chosen-id")], None)
+        .await;
+    assert_eq!(response.headers()["x-request-id"], "client-chosen-id");
+
+    let response = app.raw_request(Method::GET, "/api/v1/nowhere", None, &[], None).await;
+    let id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    assert!(!id.is_empty());
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["request_id"], id);
+}
@@ -0,0 +1,154 @@
+//! Integration tests that drive the full router against a throwaway
+//! database provisioned by `#[sqlx::test]`, which needs `DATABASE_URL`
+//! to point at a Postgres server the tests may create databases on.
+
+mod auth;
+mod concurrency;
+mod config;
+mod entry_revisions;
+mod errors;
+mod health;
+mod hierarchy;
+mod metrics;
+mod middleware;
+mod ordering;
+mod pagination;
+mod rate_limit;
+mod request_tracing;
+mod schema;
+mod search;
+mod tenant_isolation;
+mod sync_cursor;
+mod sync_protocol;
+mod sync_push;
+mod tombstones;
+mod users;
+mod validation;
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+    response::Response,
+    Router,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+use crate::auth::AuthKeys;
+use crate::build_app;
+use crate::config::{Config, Features};
+use crate::metrics::Metrics;
+use crate::rate_limit::{MemoryStore, RateLimiter};
+use crate::state::AppState;
+
+pub struct TestApp {
+    app: Router,
+}
+
+impl TestApp {
+    pub fn new(pool: PgPool) -> Self {
+        Self::with_features(pool, Features::default())
+    }
+
+    /// Rate limiting is off, since every test request comes from the same
+    /// address; use `with_config` to test it.
+    pub fn with_features(pool: PgPool, features: Features) -> Self {
+        let mut config = Config { features, ..Config::default() };
+        config.rate_limit.enabled = false;
+        Self::with_config(pool, config)
+    }
+
+    pub fn with_config(pool: PgPool, config: Config) -> Self {
+        let state = AppState {
+            pool,
+            auth: AuthKeys::new(b"test-secret", chrono::Duration::minutes(5)),
+            features: config.features,
+            metrics: Metrics::new(),
+            rate_limiter: RateLimiter::new(config.rate_limit.clone(), Arc::new(MemoryStore::default())),
+        };
+        Self { app: build_app(state, &config) }
+    }
+
+    /// Sends a request through the router and returns the status with the
+    /// decoded JSON body (`Value::Null` when the body is empty).
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let response = self.raw_request(method, uri, token, &[], body).await;
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+        };
+        (status, json)
+    }
+
+    /// Like `request`, with extra headers and the full response returned.
+    pub async fn raw_request(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        headers: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> Response {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        };
+
+        self.app.clone().oneshot(request).await.unwrap()
+    }
+
+    /// Creates an archive named `name` for the token's owner and returns
+    /// its id.
+    pub async fn create_archive(&self, token: &str, name: &str) -> String {
+        let (status, body) = self
+            .request(
+                Method::POST,
+                "/api/v1/archives",
+                Some(token),
+                Some(serde_json::json!({ "name": name, "description": "notes" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "create archive failed: {}", body);
+        body["id"].as_str().unwrap().to_string()
+    }
+
+    /// Registers a new account and returns its session token.
+    pub async fn register(&self, username: &str) -> String {
+        let (status, body) = self
+            .request(
+                Method::POST,
+                "/api/v1/auth/register",
+                None,
+                Some(serde_json::json!({
+                    "username": username,
+                    "email": format!("{}@example.com", username),
+                    "password": "correct horse battery",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "register failed: {}", body);
+        body["token"].as_str().unwrap().to_string()
+    }
+}
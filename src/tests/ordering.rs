@@ -0,0 +1,289 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+async fn create_tome(app: &TestApp, token: &str, archive_id: &str, id: &str) -> Value {
+    let (status, body) = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/archives/{}/tomes", archive_id),
+            Some(token),
+            Some(json!({ "id": id, "name": id })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body
+}
+
+/// Tome ids of an archive in sidebar order.
+async fn order(pool: &PgPool, archive_id: &str) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT id FROM tomes WHERE archive_id = $1 AND deleted_at IS NULL ORDER BY position, created_at, id",
+    )
+    .bind(archive_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+async fn move_tome(app: &TestApp, token: &str, id: &str, body: Value) -> (StatusCode, Value) {
+    app.request(Method::POST, &format!("/api/v1/tomes/{}/move", id), Some(token), Some(body))
+        .await
+}
+
+#[sqlx::test]
+async fn tomes_are_reordered_and_moved_between_archives(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    let first = app.create_archive(&token, "archive").await;
+    let second = app.create_archive(&token, "archive").await;
+
+    for (index, id) in ["tome-a", "tome-b", "tome-c"].into_iter().enumerate() {
+        let tome = create_tome(&app, &token, &first, id).await;
+        assert_eq!(tome["position"], index);
+    }
+
+    let (status, body) = move_tome(&app, &token, "tome-c", json!({ "position": 0 })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["position"], 0);
+    assert_eq!(order(&pool, &first).await, ["tome-c", "tome-a", "tome-b"]);
+
+    // Out-of-range positions clamp to the end
+    let (status, _) = move_tome(&app, &token, "tome-c", json!({ "position": 99 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(order(&pool, &first).await, ["tome-a", "tome-b", "tome-c"]);
+
+    create_tome(&app, &token, &second, "tome-x").await;
+    let (status, body) = move_tome(&app, &token, "tome-a", json!({ "archive_id": second })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["archive_id"], second);
+    assert_eq!(order(&pool, &first).await, ["tome-b", "tome-c"]);
+    assert_eq!(order(&pool, &second).await, ["tome-x", "tome-a"]);
+
+    let (status, body) = move_tome(&app, &token, "tome-a", json!({ "position": -1 })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+}
+
+#[sqlx::test]
+async fn moves_check_ownership_and_preconditions(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let archive_id = app.create_archive(&alice, "archive").await;
+    let bobs_archive = app.create_archive(&bob, "archive").await;
+    create_tome(&app, &alice, &archive_id, "tome-a").await;
+
+    let (status, body) = move_tome(&app, &alice, "tome-a", json!({ "archive_id": bobs_archive })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["details"]["resource"], "archive");
+
+    let (status, body) = move_tome(&app, &bob, "tome-a", json!({ "archive_id": bobs_archive })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["details"]["resource"], "tome");
+
+    let response = app
+        .raw_request(
+            Method::POST,
+            "/api/v1/tomes/tome-a/move",
+            Some(&alice),
+            &[("if-match", "\"0\"")],
+            Some(json!({ "position": 0 })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+}
+
+#[sqlx::test]
+async fn sync_moves_tomes_between_archives(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+
+    let archive = |id: &str| {
+        json!({
+            "id": id,
+            "name": id,
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        })
+    };
+    let tome = |id: &str, archive_id: &str, updated_at: &str, position: Option<i32>| {
+        json!({
+            "id": id,
+            "archive_id": archive_id,
+            "name": id,
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": updated_at,
+            "position": position,
+        })
+    };
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v2/sync",
+            Some(&token),
+            Some(json!({
+                "archives": [archive("archive-a"), archive("archive-b")],
+                "tomes": [
+                    tome("tome-1", "archive-a", "2025-01-01T00:00:00Z", None),
+                    tome("tome-2", "archive-a", "2025-01-01T00:00:00Z", None),
+                    tome("tome-3", "archive-b", "2025-01-01T00:00:00Z", None),
+                ],
+                "entries": [],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(order(&pool, "archive-a").await, ["tome-1", "tome-2"]);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v2/sync",
+            Some(&token),
+            Some(json!({
+                "archives": [],
+                "tomes": [
+                    tome("tome-2", "archive-b", "2025-02-01T00:00:00Z", Some(0)),
+                    tome("tome-1", "archive-missing", "2025-02-01T00:00:00Z", None),
+                ],
+                "entries": [],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let results = &body["results"]["tomes"];
+    assert_eq!(results[0]["status"], "applied");
+    assert_eq!(results[1]["status"], "rejected");
+    assert_eq!(results[1]["reason"], "missing_parent");
+
+    assert_eq!(order(&pool, "archive-a").await, ["tome-1"]);
+    assert_eq!(order(&pool, "archive-b").await, ["tome-2", "tome-3"]);
+}
+
+#[sqlx::test]
+async fn entries_move_between_tomes(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    let archive_id = app.create_archive(&token, "archive").await;
+    create_tome(&app, &token, &archive_id, "tome-a").await;
+    create_tome(&app, &token, &archive_id, "tome-b").await;
+
+    for (tome_id, id) in [("tome-a", "entry-1"), ("tome-a", "entry-2"), ("tome-b", "entry-3")] {
+        let (status, body) = app
+            .request(
+                Method::POST,
+                &format!("/api/v1/tomes/{}/entries", tome_id),
+                Some(&token),
+                Some(json!({ "id": id, "title": id, "content": "" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/entries/entry-2/move",
+            Some(&token),
+            Some(json!({ "tome_id": "tome-b", "position": 0 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["tome_id"], "tome-b");
+
+    let entries = |tome_id: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, String>(
+                "SELECT id FROM entries WHERE tome_id = $1 AND deleted_at IS NULL ORDER BY position, created_at, id",
+            )
+            .bind(tome_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+        }
+    };
+    assert_eq!(entries("tome-b").await, ["entry-2", "entry-3"]);
+
+    // Sync clients move entries by pushing a new tome_id
+    let (_, current) = app.request(Method::GET, "/api/v1/entries/entry-3", Some(&token), None).await;
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v2/sync",
+            Some(&token),
+            Some(json!({
+                "archives": [],
+                "tomes": [],
+                "entries": [{
+                    "id": "entry-3",
+                    "tome_id": "tome-a",
+                    "title": "entry-3",
+                    "content": "",
+                    "created_at": "2025-01-01T00:00:00Z",
+                    "updated_at": "2030-01-01T00:00:00Z",
+                    "base_revision": current["revision"],
+                }],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["results"]["entries"][0]["status"], "applied");
+    assert_eq!(entries("tome-a").await, ["entry-1", "entry-3"]);
+    assert_eq!(entries("tome-b").await, ["entry-2"]);
+}
+
+#[sqlx::test]
+async fn positions_out_of_range_are_rejected(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    let archive_id = app.create_archive(&token, "archive").await;
+    create_tome(&app, &token, &archive_id, "tome-a").await;
+
+    let (status, body) = move_tome(&app, &token, "tome-a", json!({ "position": i32::MAX })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+    assert_eq!(body["details"]["fields"][0]["field"], "position");
+
+    // Only the offending item is rejected; the rest of the batch applies
+    let tome = |id: &str, position: i32| json!({
+        "id": id,
+        "archive_id": archive_id,
+        "name": id,
+        "description": null,
+        "position": position,
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": "2025-01-01T00:00:00Z",
+    });
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&token),
+            Some(json!({
+                "archives": [],
+                "tomes": [tome("tome-huge", i32::MAX), tome("tome-b", 1)],
+                "entries": [],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["results"]["tomes"][0]["status"], "rejected");
+    assert_eq!(body["results"]["tomes"][0]["reason"], "invalid_field");
+    assert_eq!(body["results"]["tomes"][1]["status"], "applied");
+
+    // Appending after a sibling stored at the top of the range, as rows
+    // written before positions were checked may be, saturates
+    sqlx::query("UPDATE tomes SET position = 2147483647 WHERE id = 'tome-b'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let tome = create_tome(&app, &token, &archive_id, "tome-c").await;
+    assert_eq!(tome["position"], 2147483647);
+    assert_eq!(order(&pool, &archive_id).await, ["tome-a", "tome-b", "tome-c"]);
+}
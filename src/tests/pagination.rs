@@ -0,0 +1,148 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+/// Fetches a list page, returning the item names and the next cursor.
+async fn page(app: &TestApp, token: &str, uri: &str) -> (Vec<String>, Option<String>, Option<String>) {
+    let response = app.raw_request(Method::GET, uri, Some(token), &[], None).await;
+    assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .map(|value| value.to_str().unwrap().to_string())
+    };
+    let cursor = header("x-next-cursor");
+    let link = header("link");
+
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    let names = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["name"].as_str().unwrap().to_string())
+        .collect();
+    (names, cursor, link)
+}
+
+async fn seed_archives(app: &TestApp, token: &str, names: &[&str]) {
+    for name in names {
+        let (status, body) = app
+            .request(
+                Method::POST,
+                "/api/v1/archives",
+                Some(token),
+                Some(json!({ "name": name, "description": "" })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+}
+
+#[sqlx::test]
+async fn archives_page_through_in_sort_order(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    seed_archives(&app, &token, &["delta", "alpha", "echo", "charlie", "bravo"]).await;
+
+    let (names, cursor, link) = page(&app, &token, "/api/v1/archives?sort=name&limit=2").await;
+    assert_eq!(names, ["alpha", "bravo"]);
+    let cursor = cursor.unwrap();
+    assert_eq!(
+        link.unwrap(),
+        format!("</api/v1/archives?sort=name&limit=2&cursor={}>; rel=\"next\"", cursor)
+    );
+
+    let uri = format!("/api/v1/archives?sort=name&limit=2&cursor={}", cursor);
+    let (names, cursor, _) = page(&app, &token, &uri).await;
+    assert_eq!(names, ["charlie", "delta"]);
+
+    let uri = format!("/api/v1/archives?sort=name&limit=2&cursor={}", cursor.unwrap());
+    let (names, cursor, link) = page(&app, &token, &uri).await;
+    assert_eq!(names, ["echo"]);
+    assert_eq!(cursor, None);
+    assert_eq!(link, None);
+
+    let (names, cursor, _) = page(&app, &token, "/api/v1/archives?sort=-created_at&limit=3").await;
+    assert_eq!(names, ["bravo", "charlie", "echo"]);
+    let uri = format!("/api/v1/archives?sort=-created_at&limit=3&cursor={}", cursor.unwrap());
+    let (names, _, _) = page(&app, &token, &uri).await;
+    assert_eq!(names, ["alpha", "delta"]);
+
+    // A cursor only makes sense with the sort order that produced it
+    let (status, _) = app
+        .request(Method::GET, &uri.replace("-created_at", "name"), Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (names, cursor, _) = page(&app, &token, "/api/v1/archives").await;
+    assert_eq!(names, ["delta", "alpha", "echo", "charlie", "bravo"]);
+    assert_eq!(cursor, None);
+}
+
+#[sqlx::test]
+async fn tomes_filter_by_archive(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let tome = |id: &str, archive_id: &str| {
+        json!({
+            "id": id,
+            "archive_id": archive_id,
+            "name": id,
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        })
+    };
+    let archive = |id: &str| {
+        json!({
+            "id": id,
+            "name": id,
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        })
+    };
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&token),
+            Some(json!({
+                "archives": [archive("archive-a"), archive("archive-b")],
+                "tomes": [tome("tome-a1", "archive-a"), tome("tome-b1", "archive-b"), tome("tome-a2", "archive-a")],
+                "entries": [],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (names, _, _) = page(&app, &token, "/api/v1/tomes?archive_id=archive-a&sort=name").await;
+    assert_eq!(names, ["tome-a1", "tome-a2"]);
+
+    let (names, _, _) = page(&app, &token, "/api/v1/tomes?updated_since=2030-01-01T00:00:00Z").await;
+    assert!(names.is_empty());
+}
+
+#[sqlx::test]
+async fn list_parameters_are_validated(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    for uri in [
+        "/api/v1/archives?limit=0",
+        "/api/v1/archives?limit=1000",
+        "/api/v1/archives?sort=size",
+        "/api/v1/archives?cursor=nonsense",
+        "/api/v1/archives?updated_since=yesterday",
+    ] {
+        let (status, body) = app.request(Method::GET, uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        assert_eq!(body["code"], "bad_request");
+    }
+}
@@ -0,0 +1,103 @@
+use axum::http::{header, Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+use crate::config::{Budget, Config};
+
+// Budgets small enough to run out of, refilling one request per second
+fn limited(sync: u32, auth: u32, crud: u32) -> Config {
+    let mut config = Config::default();
+    config.rate_limit.trusted_proxies = 1;
+    config.rate_limit.sync = Budget { burst: sync, per_minute: 60 };
+    config.rate_limit.auth = Budget { burst: auth, per_minute: 60 };
+    config.rate_limit.crud = Budget { burst: crud, per_minute: 60 };
+    config
+}
+
+async fn login(app: &TestApp, ip: &str) -> StatusCode {
+    let body = json!({ "username": "nobody", "password": "wrong password" });
+    app.raw_request(Method::POST, "/api/v1/auth/login", None, &[("x-forwarded-for", ip)], Some(body))
+        .await
+        .status()
+}
+
+#[sqlx::test]
+async fn exhausted_budgets_answer_429_with_retry_after(pool: PgPool) {
+    let app = TestApp::with_config(pool, limited(10, 2, 10));
+
+    assert_eq!(login(&app, "203.0.113.1").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(login(&app, "203.0.113.1").await, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .raw_request(
+            Method::POST,
+            "/api/v1/auth/login",
+            None,
+            &[("x-forwarded-for", "203.0.113.1")],
+            Some(json!({ "username": "nobody", "password": "wrong password" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["code"], "rate_limited");
+    assert_eq!(body["details"]["scope"], "auth");
+
+    // Another address has its own bucket
+    assert_eq!(login(&app, "203.0.113.2").await, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn made_up_forwarded_addresses_do_not_reset_the_budget(pool: PgPool) {
+    let app = TestApp::with_config(pool.clone(), limited(10, 2, 10));
+
+    // The proxy appends the real address after whatever the client sent
+    assert_eq!(login(&app, "198.51.100.1, 203.0.113.1").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(login(&app, "198.51.100.2, 203.0.113.1").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(login(&app, "198.51.100.3, 203.0.113.1").await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(login(&app, "203.0.113.1").await, StatusCode::TOO_MANY_REQUESTS);
+
+    // Behind two proxies the client is the second address from the right
+    let mut config = limited(10, 1, 10);
+    config.rate_limit.trusted_proxies = 2;
+    let app = TestApp::with_config(pool, config);
+    assert_eq!(login(&app, "198.51.100.1, 203.0.113.1, 10.0.0.1").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(login(&app, "198.51.100.2, 203.0.113.1, 10.0.0.2").await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(login(&app, "203.0.113.2, 10.0.0.1").await, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn authenticated_callers_are_limited_per_user_and_route_group(pool: PgPool) {
+    let app = TestApp::with_config(pool, limited(1, 10, 2));
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    for _ in 0..2 {
+        let (status, _) = app.request(Method::GET, "/api/v1/archives", Some(&alice), None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, _) = app.request(Method::GET, "/api/v1/archives", Some(&alice), None).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Same address, different user
+    let (status, _) = app.request(Method::GET, "/api/v1/archives", Some(&bob), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Sync has a budget of its own, shared by both API versions
+    let (status, _) = app.request(Method::GET, "/api/v1/sync", Some(&alice), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.request(Method::GET, "/api/v2/sync", Some(&alice), None).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[sqlx::test]
+async fn probes_are_never_limited(pool: PgPool) {
+    let app = TestApp::with_config(pool, limited(1, 1, 1));
+
+    for _ in 0..3 {
+        let (status, _) = app.request(Method::GET, "/healthz", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,131 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+// Collects everything the subscriber writes, one JSON event per line
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    fn events(&self) -> Vec<Value> {
+        let bytes = self.0.lock().unwrap();
+        String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+fn capture() -> (Captured, tracing::subscriber::DefaultGuard) {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_writer(move || writer.clone())
+        .finish();
+    (captured, tracing::subscriber::set_default(subscriber))
+}
+
+#[sqlx::test]
+async fn completed_requests_are_logged_with_their_context(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let (_, me) = app.request(Method::GET, "/api/v1/users/me", Some(&token), None).await;
+
+    let (captured, _guard) = capture();
+    let response = app
+        .raw_request(Method::GET, "/api/v1/users/me", Some(&token), &[("x-request-id", "trace-me")], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events = captured.events();
+    let completed = events
+        .iter()
+        .find(|event| event["fields"]["message"] == "Request completed")
+        .unwrap_or_else(|| panic!("no completion event in {:?}", events));
+    let span = &completed["span"];
+    assert_eq!(span["name"], "request");
+    assert_eq!(span["method"], "GET");
+    assert_eq!(span["route"], "/api/v1/users/me");
+    assert_eq!(span["request_id"], "trace-me");
+    assert_eq!(span["user_id"], me["id"]);
+    assert_eq!(span["status"], 200);
+    assert!(span["latency_ms"].is_u64());
+}
+
+#[sqlx::test]
+async fn server_errors_are_logged_at_error_level(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    sqlx::query("DROP TABLE archives CASCADE").execute(&pool).await.unwrap();
+
+    let (captured, _guard) = capture();
+    let (status, body) = app.request(Method::GET, "/api/v1/archives", Some(&token), None).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    let events = captured.events();
+    let internal = events
+        .iter()
+        .find(|event| event["fields"]["message"] == "Internal error")
+        .unwrap_or_else(|| panic!("no internal error event in {:?}", events));
+    assert_eq!(internal["level"], "ERROR");
+    assert_eq!(internal["span"]["request_id"], body["request_id"]);
+
+    let failed = events
+        .iter()
+        .find(|event| event["fields"]["message"] == "Request failed")
+        .unwrap_or_else(|| panic!("no failure event in {:?}", events));
+    assert_eq!(failed["span"]["status"], 500);
+}
+
+#[sqlx::test]
+async fn sync_push_failures_name_the_failing_item(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    sqlx::query("DROP TABLE archives CASCADE").execute(&pool).await.unwrap();
+
+    let (captured, _guard) = capture();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&token),
+            Some(json!({
+                "archives": [{
+                    "id": "archive-broken",
+                    "name": "Broken",
+                    "description": null,
+                    "created_at": "2025-01-01T00:00:00Z",
+                    "updated_at": "2025-01-01T00:00:00Z",
+                }],
+                "tomes": [],
+                "entries": [],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    let events = captured.events();
+    let internal = events
+        .iter()
+        .find(|event| event["fields"]["message"] == "Internal error")
+        .unwrap_or_else(|| panic!("no internal error event in {:?}", events));
+    assert_eq!(internal["span"]["name"], "sync_item");
+    assert_eq!(internal["span"]["kind"], "archive");
+    assert_eq!(internal["span"]["id"], "archive-broken");
+}
@@ -0,0 +1,243 @@
+//! Catches drift between the migrations and the queries in the code: the
+//! database under test is built from every migration, and each route is
+//! called once with a request that should succeed.
+
+use std::collections::BTreeSet;
+
+use axum::http::{Method, StatusCode};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{postgres::PgRow, FromRow, PgPool};
+
+use super::TestApp;
+use crate::models::{archive::Archive, entry::Entry, tome::Tome, user::UserProfile};
+use crate::pagination::Paginated;
+
+async fn column_is_nullable(pool: &PgPool, table: &str, column: &str) -> Option<bool> {
+    sqlx::query_scalar(
+        "SELECT is_nullable = 'YES' FROM information_schema.columns
+         WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2",
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn migrations_define_the_columns_the_code_uses(pool: PgPool) {
+    assert_eq!(column_is_nullable(&pool, "entries", "title").await, Some(false));
+    assert_eq!(column_is_nullable(&pool, "entries", "name").await, None);
+    assert_eq!(column_is_nullable(&pool, "users", "updated_at").await, Some(false));
+    assert_eq!(column_is_nullable(&pool, "users", "is_active").await, Some(false));
+
+    let id_type: String = sqlx::query_scalar(
+        "SELECT data_type FROM information_schema.columns
+         WHERE table_schema = current_schema() AND table_name = 'users' AND column_name = 'id'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(id_type, "uuid");
+}
+
+/// Reads one row of `table` through `T::COLUMNS` and checks that the list
+/// names exactly the fields `T` has.
+async fn check_columns<T>(pool: &PgPool, table: &str)
+where
+    T: Paginated + Serialize + for<'r> FromRow<'r, PgRow> + Send + Unpin,
+{
+    let row: T = sqlx::query_as(&format!("SELECT {} FROM {} LIMIT 1", T::COLUMNS, table))
+        .fetch_one(pool)
+        .await
+        .unwrap_or_else(|e| panic!("{} columns do not decode: {}", table, e));
+    let fields: BTreeSet<String> = match serde_json::to_value(&row).unwrap() {
+        Value::Object(fields) => fields.into_iter().map(|(field, _)| field).collect(),
+        other => panic!("{} row is not an object: {}", table, other),
+    };
+    let columns: BTreeSet<String> = T::COLUMNS.split(", ").map(String::from).collect();
+    assert_eq!(columns, fields, "{}", table);
+}
+
+#[sqlx::test]
+async fn list_columns_match_their_row_types(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    let archive_id = app.create_archive(&token, "archive").await;
+    let tome = call(
+        &app,
+        &token,
+        Method::POST,
+        &format!("/api/v1/archives/{}/tomes", archive_id),
+        Some(json!({ "name": "tome" })),
+        StatusCode::OK,
+    )
+    .await;
+    call(
+        &app,
+        &token,
+        Method::POST,
+        &format!("/api/v1/tomes/{}/entries", tome["id"].as_str().unwrap()),
+        Some(json!({ "title": "entry", "content": "" })),
+        StatusCode::OK,
+    )
+    .await;
+
+    check_columns::<UserProfile>(&pool, "users").await;
+    check_columns::<Archive>(&pool, "archives").await;
+    check_columns::<Tome>(&pool, "tomes").await;
+    check_columns::<Entry>(&pool, "entries").await;
+}
+
+/// Calls `uri` and fails the test unless it answers with `expected`.
+async fn call(app: &TestApp, token: &str, method: Method, uri: &str, body: Option<Value>, expected: StatusCode) -> Value {
+    let (status, body) = app.request(method.clone(), uri, Some(token), body).await;
+    assert_eq!(status, expected, "{} {}: {}", method, uri, body);
+    body
+}
+
+#[sqlx::test]
+async fn every_route_works_against_the_migrated_schema(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let ok = StatusCode::OK;
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/auth/login",
+            None,
+            Some(json!({ "username": "nobody", "password": "whatever1" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+    let token = app.register("alice").await;
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/auth/login",
+            None,
+            Some(json!({ "username": "alice", "password": "correct horse battery" })),
+        )
+        .await;
+    assert_eq!(status, ok);
+
+    // Users
+    let me = call(&app, &token, Method::GET, "/api/v1/users/me", None, ok).await;
+    let user_uri = format!("/api/v1/users/{}", me["id"].as_str().unwrap());
+    call(&app, &token, Method::GET, &user_uri, None, ok).await;
+    call(
+        &app,
+        &token,
+        Method::PUT,
+        &user_uri,
+        Some(json!({ "username": "alice", "email": "alice@example.org" })),
+        ok,
+    )
+    .await;
+    sqlx::query("UPDATE users SET is_admin = true WHERE username = 'alice'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    call(&app, &token, Method::GET, "/api/v1/users?sort=-updated_at", None, ok).await;
+    let other = call(
+        &app,
+        &token,
+        Method::POST,
+        "/api/v1/users",
+        Some(json!({ "username": "carol", "email": "carol@example.com", "password": "long enough" })),
+        ok,
+    )
+    .await;
+    assert!(other.get("password_hash").is_none());
+
+    // Archives
+    let archive = call(&app, &token, Method::POST, "/api/v1/archives", Some(json!({ "name": "archive" })), ok).await;
+    let archive_id = archive["id"].as_str().unwrap().to_string();
+    let archive_uri = format!("/api/v1/archives/{}", archive_id);
+    call(&app, &token, Method::GET, "/api/v1/archives?sort=name", None, ok).await;
+    call(&app, &token, Method::GET, &archive_uri, None, ok).await;
+    call(&app, &token, Method::PUT, &archive_uri, Some(json!({ "name": "renamed" })), ok).await;
+    call(&app, &token, Method::PATCH, &archive_uri, Some(json!({ "description": "notes" })), ok).await;
+
+    // Tomes
+    let tome = call(
+        &app,
+        &token,
+        Method::POST,
+        "/api/v1/tomes",
+        Some(json!({ "archive_id": archive_id, "name": "tome" })),
+        ok,
+    )
+    .await;
+    let tome_id = tome["id"].as_str().unwrap().to_string();
+    let tome_uri = format!("/api/v1/tomes/{}", tome_id);
+    call(&app, &token, Method::POST, &format!("{}/tomes", archive_uri), Some(json!({ "name": "second" })), ok).await;
+    call(&app, &token, Method::GET, &format!("{}/tomes", archive_uri), None, ok).await;
+    call(&app, &token, Method::GET, &format!("/api/v1/tomes?archive_id={}", archive_id), None, ok).await;
+    call(&app, &token, Method::GET, &tome_uri, None, ok).await;
+    call(&app, &token, Method::PUT, &tome_uri, Some(json!({ "name": "tome", "description": null })), ok).await;
+    call(&app, &token, Method::PATCH, &tome_uri, Some(json!({ "name": "patched" })), ok).await;
+    call(&app, &token, Method::POST, &format!("{}/move", tome_uri), Some(json!({ "position": 1 })), ok).await;
+
+    // Entries
+    let entry = call(
+        &app,
+        &token,
+        Method::POST,
+        "/api/v1/entries",
+        Some(json!({ "tome_id": tome_id, "title": "entry", "content": "first draft" })),
+        ok,
+    )
+    .await;
+    let entry_id = entry["id"].as_str().unwrap().to_string();
+    let entry_uri = format!("/api/v1/entries/{}", entry_id);
+    call(
+        &app,
+        &token,
+        Method::POST,
+        &format!("{}/entries", tome_uri),
+        Some(json!({ "title": "second", "content": "" })),
+        ok,
+    )
+    .await;
+    call(&app, &token, Method::GET, &format!("{}/entries", tome_uri), None, ok).await;
+    call(&app, &token, Method::GET, &format!("/api/v1/entries?tome_id={}&sort=name", tome_id), None, ok).await;
+    call(&app, &token, Method::GET, &entry_uri, None, ok).await;
+    call(&app, &token, Method::PUT, &entry_uri, Some(json!({ "title": "entry", "content": "second draft" })), ok).await;
+    call(&app, &token, Method::PATCH, &entry_uri, Some(json!({ "content": "third draft" })), ok).await;
+    call(&app, &token, Method::POST, &format!("{}/move", entry_uri), Some(json!({ "position": 0 })), ok).await;
+
+    // Revisions
+    let revisions = call(&app, &token, Method::GET, &format!("{}/revisions", entry_uri), None, ok).await;
+    let first = revisions.as_array().unwrap().last().unwrap()["revision"].as_i64().unwrap();
+    let revision_uri = format!("{}/revisions/{}", entry_uri, first);
+    call(&app, &token, Method::GET, &revision_uri, None, ok).await;
+    call(&app, &token, Method::GET, &format!("{}/diff", revision_uri), None, ok).await;
+    call(&app, &token, Method::POST, &format!("{}/restore", revision_uri), None, ok).await;
+
+    // Reading views
+    call(&app, &token, Method::GET, &format!("{}/tree", archive_uri), None, ok).await;
+    call(&app, &token, Method::GET, "/api/v1/search?q=draft", None, ok).await;
+
+    // Sync, in both protocol versions
+    for version in ["v1", "v2"] {
+        let uri = format!("/api/{}/sync", version);
+        let pulled = call(&app, &token, Method::GET, &uri, None, ok).await;
+        call(
+            &app,
+            &token,
+            Method::POST,
+            &uri,
+            Some(json!({ "archives": [], "tomes": [], "entries": pulled["entries"] })),
+            ok,
+        )
+        .await;
+    }
+
+    // Deletes, children first
+    call(&app, &token, Method::DELETE, &entry_uri, None, ok).await;
+    call(&app, &token, Method::DELETE, &tome_uri, None, ok).await;
+    call(&app, &token, Method::DELETE, &archive_uri, None, ok).await;
+    call(&app, &token, Method::DELETE, &user_uri, None, StatusCode::NO_CONTENT).await;
+}
@@ -0,0 +1,170 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::TestApp;
+
+async fn seed(app: &TestApp, token: &str) {
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(token),
+            Some(json!({
+                "archives": [{
+                    "id": "archive-garden",
+                    "name": "Garden",
+                    "description": "Planting notes for the allotment",
+                    "created_at": "2025-01-01T00:00:00Z",
+                    "updated_at": "2025-01-01T00:00:00Z",
+                }],
+                "tomes": [{
+                    "id": "tome-tomatoes",
+                    "archive_id": "archive-garden",
+                    "name": "Tomatoes",
+                    "description": "Which tomato varieties did well",
+                    "created_at": "2025-01-01T00:00:00Z",
+                    "updated_at": "2025-01-01T00:00:00Z",
+                }],
+                "entries": [],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[sqlx::test]
+async fn search_ranks_names_and_reports_the_path(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    seed(&app, &token).await;
+
+    let (status, body) = app
+        .request(Method::GET, "/api/v1/search?q=tomatoes", Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let hits = body.as_array().unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0]["kind"], "tome");
+    assert_eq!(hits[0]["id"], "tome-tomatoes");
+    assert_eq!(hits[0]["snippet"], "Which <mark>tomato</mark> varieties did well");
+    assert_eq!(
+        hits[0]["path"],
+        json!([{ "kind": "archive", "id": "archive-garden", "name": "Garden" }])
+    );
+
+    let (_, body) = app
+        .request(Method::GET, "/api/v1/search?q=planting%20OR%20tomato", Some(&token), None)
+        .await;
+    let kinds: Vec<_> = body.as_array().unwrap().iter().map(|hit| hit["kind"].clone()).collect();
+    assert_eq!(kinds, vec![json!("tome"), json!("archive")]);
+}
+
+#[sqlx::test]
+async fn search_only_sees_the_callers_data(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    seed(&app, &alice).await;
+
+    let (status, body) = app
+        .request(Method::GET, "/api/v1/search?q=garden", Some(&bob), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!([]));
+}
+
+#[sqlx::test]
+async fn search_requires_a_query(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    for uri in ["/api/v1/search?q=", "/api/v1/search?q=%20", "/api/v1/search?q=x&limit=0"] {
+        let (status, _) = app.request(Method::GET, uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
+
+fn entry(id: &str, title: &str, content: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "tome_id": "tome-tomatoes",
+        "title": title,
+        "content": content,
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": "2025-01-01T00:00:00Z",
+    })
+}
+
+#[sqlx::test]
+async fn entry_hits_carry_the_full_path_and_stay_with_their_owner(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    seed(&app, &alice).await;
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&alice),
+            Some(json!({
+                "archives": [],
+                "tomes": [],
+                "entries": [
+                    entry("entry-basil", "Basil", "Sow indoors in spring"),
+                    entry("entry-companions", "Companions", "Plant basil next to the tomatoes"),
+                    entry("entry-gone", "Basil again", "Deleted before anyone searched"),
+                ],
+                "deleted": { "entries": [{ "id": "entry-gone", "deleted_at": "2025-02-01T00:00:00Z" }] },
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Bob's data matches the same query
+    let archive_id = app.create_archive(&bob, "Basil farm").await;
+    let (_, tome) = app
+        .request(
+            Method::POST,
+            &format!("/api/v1/archives/{}/tomes", archive_id),
+            Some(&bob),
+            Some(json!({ "name": "Basil" })),
+        )
+        .await;
+    app.request(
+        Method::POST,
+        &format!("/api/v1/tomes/{}/entries", tome["id"].as_str().unwrap()),
+        Some(&bob),
+        Some(json!({ "title": "Basil", "content": "Bob's basil" })),
+    )
+    .await;
+
+    let (status, body) = app
+        .request(Method::GET, "/api/v1/search?q=basil", Some(&alice), None)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let ids: Vec<_> = body.as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    // A title match outranks a mention in the content
+    assert_eq!(ids, vec![json!("entry-basil"), json!("entry-companions")]);
+
+    let hit = &body[1];
+    assert_eq!(hit["kind"], "entry");
+    assert_eq!(hit["title"], "Companions");
+    assert_eq!(hit["snippet"], "Plant <mark>basil</mark> next to the tomatoes");
+    assert_eq!(
+        hit["path"],
+        json!([
+            { "kind": "archive", "id": "archive-garden", "name": "Garden" },
+            { "kind": "tome", "id": "tome-tomatoes", "name": "Tomatoes" },
+        ])
+    );
+
+    let (_, body) = app
+        .request(Method::GET, "/api/v1/search?q=basil", Some(&bob), None)
+        .await;
+    let hits = body.as_array().unwrap();
+    assert_eq!(hits.len(), 3, "{}", body);
+    assert!(hits.iter().all(|hit| hit["id"] != "entry-basil" && hit["id"] != "entry-companions"));
+}
@@ -0,0 +1,172 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::TestApp;
+use crate::sync::SyncCursor;
+
+async fn revisions(pool: &PgPool) -> Vec<i64> {
+    sqlx::query_scalar(
+        "SELECT revision FROM archives UNION ALL SELECT revision FROM tomes ORDER BY 1"
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[test]
+fn cursor_tokens_round_trip_and_reject_garbage() {
+    let cursor = SyncCursor::new(42);
+    assert_eq!(SyncCursor::decode(&cursor.encode()), Some(cursor));
+
+    assert_eq!(SyncCursor::decode("42"), None);
+    assert_eq!(SyncCursor::decode("not base64!"), None);
+    assert_eq!(SyncCursor::decode(&SyncCursor::new(-1).encode()), None);
+}
+
+#[sqlx::test]
+async fn pull_rejects_malformed_cursor(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (status, _) = app
+        .request(Method::GET, "/api/v1/sync?cursor=bogus", Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn every_write_advances_the_users_revision(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+
+    let (_, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/archives",
+            Some(&token),
+            Some(json!({ "name": "archive", "description": "notes" })),
+        )
+        .await;
+    let archive_id = body["id"].as_str().unwrap().to_string();
+    assert_eq!(revisions(&pool).await, vec![1]);
+
+    app.request(
+        Method::POST,
+        "/api/v1/sync",
+        Some(&token),
+        Some(json!({
+            "archives": [],
+            "tomes": [{
+                "id": "tome-1",
+                "archive_id": archive_id,
+                "name": "tome",
+                "description": null,
+                "created_at": "2025-01-01T00:00:00Z",
+                "updated_at": "2025-01-01T00:00:00Z",
+            }],
+            "entries": [],
+        })),
+    )
+    .await;
+    assert_eq!(revisions(&pool).await, vec![1, 2]);
+
+    app.request(Method::DELETE, &format!("/api/v1/archives/{}", archive_id), Some(&token), None)
+        .await;
+    let after_delete = revisions(&pool).await;
+    assert!(after_delete.iter().all(|revision| *revision > 2), "{:?}", after_delete);
+
+    // Other users have their own sequence
+    let bob = app.register("bob").await;
+    app.request(
+        Method::POST,
+        "/api/v1/archives",
+        Some(&bob),
+        Some(json!({ "name": "bob's", "description": "notes" })),
+    )
+    .await;
+    let bob_revision: i64 = sqlx::query_scalar("SELECT sync_revision FROM users WHERE username = 'bob'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(bob_revision, 1);
+}
+
+#[sqlx::test]
+async fn pushes_that_change_nothing_leave_the_revision_alone(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    let push = |name: &str, updated_at: &str| json!({
+        "archives": [{
+            "id": "archive-1",
+            "name": name,
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": updated_at,
+        }],
+        "tomes": [],
+        "entries": [],
+    });
+    let user_revision = || async {
+        sqlx::query_scalar::<_, i64>("SELECT sync_revision FROM users WHERE username = 'alice'")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    app.request(Method::POST, "/api/v1/sync", Some(&token), Some(push("first", "2025-06-01T00:00:00Z")))
+        .await;
+    assert_eq!(user_revision().await, 1);
+
+    // Stale, so the conflicting insert is dropped
+    let (_, body) = app
+        .request(Method::POST, "/api/v1/sync", Some(&token), Some(push("older", "2025-03-01T00:00:00Z")))
+        .await;
+    assert_eq!(body["results"]["archives"][0]["status"], "stale");
+    assert_eq!(user_revision().await, 1);
+
+    // An accepted update through the conflict path counts once
+    app.request(Method::POST, "/api/v1/sync", Some(&token), Some(push("newer", "2025-09-01T00:00:00Z")))
+        .await;
+    assert_eq!(user_revision().await, 2);
+    assert_eq!(revisions(&pool).await, vec![2]);
+}
+
+#[sqlx::test]
+async fn pull_rejects_out_of_range_page_size(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    for limit in ["0", "-5", "100000"] {
+        let (status, _) = app
+            .request(Method::GET, &format!("/api/v1/sync?limit={}", limit), Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "limit={}", limit);
+    }
+}
+
+#[sqlx::test]
+async fn pull_pages_through_changes_in_revision_order(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    for name in ["one", "two", "three"] {
+        app.request(Method::POST, "/api/v1/archives", Some(&token), Some(json!({ "name": name })))
+            .await;
+    }
+
+    let mut names = Vec::new();
+    let mut uri = "/api/v2/sync?limit=2".to_string();
+    loop {
+        let (status, body) = app.request(Method::GET, &uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        for archive in body["archives"].as_array().unwrap() {
+            names.push(archive["name"].as_str().unwrap().to_string());
+        }
+        if body["has_more"] == false {
+            break;
+        }
+        uri = format!("/api/v2/sync?limit=2&cursor={}", body["cursor"].as_str().unwrap());
+    }
+    assert_eq!(names, ["one", "two", "three"]);
+}
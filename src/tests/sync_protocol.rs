@@ -0,0 +1,149 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+fn push_body(tome: Value) -> Value {
+    json!({
+        "archives": [{
+            "id": "archive-1",
+            "name": "archive",
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        }],
+        "tomes": [tome],
+        "entries": [],
+    })
+}
+
+fn tome(name_field: &str, name: &str, updated_at: &str) -> Value {
+    let mut tome = json!({
+        "id": "tome-1",
+        "archive_id": "archive-1",
+        "description": null,
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": updated_at,
+    });
+    tome[name_field] = json!(name);
+    tome
+}
+
+#[sqlx::test]
+async fn v1_clients_send_and_receive_tome_titles(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&token),
+            Some(push_body(tome("title", "from v1", "2025-06-01T00:00:00Z"))),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["results"]["tomes"][0]["status"], "applied");
+
+    // Stale pushes get the server's copy back in the client's version
+    let (_, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&token),
+            Some(push_body(tome("title", "older", "2025-02-01T00:00:00Z"))),
+        )
+        .await;
+    let result = &body["results"]["tomes"][0];
+    assert_eq!(result["status"], "stale");
+    assert_eq!(result["current"]["title"], "from v1");
+
+    // v2 clients see the same tome under `name` only
+    let (_, body) = app
+        .request(
+            Method::POST,
+            "/api/v2/sync",
+            Some(&token),
+            Some(push_body(tome("name", "older", "2025-02-01T00:00:00Z"))),
+        )
+        .await;
+    let current = &body["results"]["tomes"][0]["current"];
+    assert_eq!(current["name"], "from v1");
+    assert!(current.get("title").is_none());
+}
+
+#[sqlx::test]
+async fn v2_requires_tome_names(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v2/sync",
+            Some(&token),
+            Some(push_body(tome("title", "legacy", "2025-06-01T00:00:00Z"))),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v2/sync",
+            Some(&token),
+            Some(push_body(tome("name", "current", "2025-06-01T00:00:00Z"))),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["results"]["tomes"][0]["status"], "applied");
+}
+
+#[sqlx::test]
+async fn protocol_header_overrides_the_path_default(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let empty = || Some(json!({ "archives": [], "tomes": [], "entries": [] }));
+
+    let response = app
+        .raw_request(Method::POST, "/api/v1/sync", Some(&token), &[("x-sync-protocol", "2")], empty())
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-sync-protocol"], "2");
+
+    let response = app.raw_request(Method::POST, "/api/v2/sync", Some(&token), &[], empty()).await;
+    assert_eq!(response.headers()["x-sync-protocol"], "2");
+
+    let response = app.raw_request(Method::POST, "/api/v1/sync", Some(&token), &[], empty()).await;
+    assert_eq!(response.headers()["x-sync-protocol"], "1");
+
+    let response = app
+        .raw_request(Method::POST, "/api/v1/sync", Some(&token), &[("x-sync-protocol", "3")], empty())
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn pulls_are_written_in_the_requested_version(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    app.request(
+        Method::POST,
+        "/api/v2/sync",
+        Some(&token),
+        Some(push_body(tome("name", "shared", "2025-06-01T00:00:00Z"))),
+    )
+    .await;
+
+    let response = app.raw_request(Method::GET, "/api/v1/sync", Some(&token), &[], None).await;
+    assert_eq!(response.headers()["x-sync-protocol"], "1");
+    let (_, body) = app.request(Method::GET, "/api/v1/sync", Some(&token), None).await;
+    assert_eq!(body["tomes"][0]["title"], "shared");
+    assert_eq!(body["tomes"][0]["name"], "shared");
+
+    let (_, body) = app.request(Method::GET, "/api/v2/sync", Some(&token), None).await;
+    assert_eq!(body["tomes"][0]["name"], "shared");
+    assert!(body["tomes"][0].get("title").is_none());
+}
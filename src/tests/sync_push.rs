@@ -0,0 +1,224 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+fn archive(id: &str, name: &str, updated_at: &str) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "description": null,
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": updated_at,
+    })
+}
+
+async fn push(app: &TestApp, token: &str, body: Value) -> Value {
+    let (status, body) = app.request(Method::POST, "/api/v1/sync", Some(token), Some(body)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["results"].clone()
+}
+
+#[sqlx::test]
+async fn push_reports_applied_and_stale_items(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let results = push(&app, &token, json!({
+        "archives": [archive("archive-1", "first", "2025-06-01T00:00:00Z")],
+        "tomes": [],
+        "entries": [],
+    }))
+    .await;
+    assert_eq!(results["archives"][0]["status"], "applied");
+
+    let results = push(&app, &token, json!({
+        "archives": [archive("archive-1", "older edit", "2025-03-01T00:00:00Z")],
+        "tomes": [],
+        "entries": [],
+    }))
+    .await;
+    let result = &results["archives"][0];
+    assert_eq!(result["status"], "stale");
+    assert_eq!(result["current"]["name"], "first");
+    assert_eq!(result["current"]["updated_at"], "2025-06-01T00:00:00+00:00");
+}
+
+#[sqlx::test]
+async fn push_rejects_items_it_cannot_apply(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    let tome = |id: &str, archive_id: &str| json!({
+        "id": id,
+        "archive_id": archive_id,
+        "name": "tome",
+        "description": null,
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": "2025-01-01T00:00:00Z",
+    });
+
+    push(&app, &alice, json!({
+        "archives": [archive("archive-1", "alice", "2025-06-01T00:00:00Z")],
+        "tomes": [tome("tome-alice", "archive-1")],
+        "entries": [],
+    }))
+    .await;
+
+    let results = push(&app, &bob, json!({
+        "archives": [
+            archive("archive-1", "bob", "2999-01-01T00:00:00Z"),
+            archive("archive-2", "bad clock", "yesterday"),
+            archive("archive-3", "bob's own", "2025-01-01T00:00:00Z"),
+        ],
+        "tomes": [tome("tome-1", "archive-1"), tome("tome-alice", "archive-3")],
+        "entries": [],
+        "deleted": {
+            "archives": [{ "id": "archive-1", "deleted_at": "2999-01-01T00:00:00Z" }],
+        },
+    }))
+    .await;
+
+    assert_eq!(results["archives"][0]["status"], "rejected");
+    assert_eq!(results["archives"][0]["reason"], "id_conflict");
+    assert!(results["archives"][0].get("current").is_none());
+    assert_eq!(results["archives"][1]["reason"], "invalid_timestamp");
+    assert_eq!(results["tomes"][0]["status"], "rejected");
+    assert_eq!(results["tomes"][0]["reason"], "missing_parent");
+    assert_eq!(results["tomes"][1]["reason"], "id_conflict");
+    assert_eq!(results["deleted"]["archives"][0]["reason"], "not_found");
+}
+
+#[sqlx::test]
+async fn push_reports_edits_to_deleted_items(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let results = push(&app, &token, json!({
+        "archives": [archive("archive-1", "first", "2025-06-01T00:00:00Z")],
+        "tomes": [],
+        "entries": [],
+        "deleted": {
+            "archives": [{ "id": "archive-1", "deleted_at": "2025-07-01T00:00:00Z" }],
+        },
+    }))
+    .await;
+    assert_eq!(results["deleted"]["archives"][0]["status"], "applied");
+
+    let results = push(&app, &token, json!({
+        "archives": [archive("archive-1", "edited", "2025-08-01T00:00:00Z")],
+        "tomes": [],
+        "entries": [],
+    }))
+    .await;
+    assert_eq!(results["archives"][0]["status"], "rejected");
+    assert_eq!(results["archives"][0]["reason"], "deleted");
+}
+
+fn entry(content: &str, base_revision: i64) -> Value {
+    json!({
+        "id": "entry-1",
+        "tome_id": "tome-1",
+        "title": "notes",
+        "content": content,
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": "2025-01-01T00:00:00Z",
+        "base_revision": base_revision,
+    })
+}
+
+#[sqlx::test]
+async fn edits_against_a_base_revision_are_merged(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let results = push(&app, &token, json!({
+        "archives": [archive("archive-1", "archive", "2025-01-01T00:00:00Z")],
+        "tomes": [{
+            "id": "tome-1",
+            "archive_id": "archive-1",
+            "name": "tome",
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        }],
+        "entries": [entry("intro\n\nmiddle\n\noutro\n", 0)],
+    }))
+    .await;
+    assert_eq!(results["entries"][0]["status"], "applied");
+    let (_, current) = app.request(Method::GET, "/api/v1/entries/entry-1", Some(&token), None).await;
+    let base = current["revision"].as_i64().unwrap();
+
+    // Another device edits the top while this one edits the bottom
+    app.request(
+        Method::PATCH,
+        "/api/v1/entries/entry-1",
+        Some(&token),
+        Some(json!({ "content": "better intro\n\nmiddle\n\noutro\n" })),
+    )
+    .await;
+    let results = push(&app, &token, json!({
+        "archives": [],
+        "tomes": [],
+        "entries": [entry("intro\n\nmiddle\n\noutro\nps\n", base)],
+    }))
+    .await;
+    let result = &results["entries"][0];
+    assert_eq!(result["status"], "merged", "{}", result);
+    assert_eq!(result["current"]["content"], "better intro\n\nmiddle\n\noutro\nps\n");
+
+    // Overlapping edits keep the server's text and save a conflict copy
+    let results = push(&app, &token, json!({
+        "archives": [],
+        "tomes": [],
+        "entries": [entry("worse intro\n\nmiddle\n\noutro\n", base)],
+    }))
+    .await;
+    let result = &results["entries"][0];
+    assert_eq!(result["status"], "conflict", "{}", result);
+    assert_eq!(result["current"]["content"], "better intro\n\nmiddle\n\noutro\nps\n");
+    assert_eq!(result["conflict_copy"]["title"], "notes (conflicted copy)");
+    assert_eq!(result["conflict_copy"]["content"], "worse intro\n\nmiddle\n\noutro\n");
+    assert_eq!(result["conflict_copy"]["tome_id"], "tome-1");
+}
+
+#[sqlx::test]
+async fn overlong_fields_reject_only_their_item(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let long = "x".repeat(256);
+
+    let results = push(&app, &token, json!({
+        "archives": [
+            archive(&long, "long id", "2025-01-01T00:00:00Z"),
+            archive("archive-1", &long, "2025-01-01T00:00:00Z"),
+            archive("archive-2", "fine", "2025-01-01T00:00:00Z"),
+        ],
+        "tomes": [{
+            "id": "tome-1",
+            "archive_id": "archive-2",
+            "name": "tome",
+            "description": null,
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        }],
+        "entries": [{
+            "id": "entry-1",
+            "tome_id": "tome-1",
+            "title": long,
+            "content": "",
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        }],
+    }))
+    .await;
+
+    assert_eq!(results["archives"][0]["reason"], "invalid_field");
+    assert_eq!(results["archives"][1]["reason"], "invalid_field");
+    assert_eq!(results["archives"][2]["status"], "applied");
+    assert_eq!(results["tomes"][0]["status"], "applied");
+    assert_eq!(results["entries"][0]["status"], "rejected");
+    assert_eq!(results["entries"][0]["reason"], "invalid_field");
+}
@@ -0,0 +1,145 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+fn tome_payload(id: &str, archive_id: &str) -> Value {
+    json!({ "id": id, "archive_id": archive_id, "name": "tome", "description": null })
+}
+
+#[sqlx::test]
+async fn requests_without_a_token_are_rejected(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let (status, _) = app.request(Method::GET, "/api/v1/archives", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app
+        .request(Method::GET, "/api/v1/archives", Some("not-a-token"), None)
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn archives_are_invisible_to_other_tenants(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    let archive_id = app.create_archive(&alice, "alice's archive").await;
+    let uri = format!("/api/v1/archives/{}", archive_id);
+
+    let (status, body) = app.request(Method::GET, "/api/v1/archives", Some(&bob), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!([]));
+
+    let (status, _) = app.request(Method::GET, &uri, Some(&bob), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = app
+        .request(
+            Method::PUT,
+            &uri,
+            Some(&bob),
+            Some(json!({ "name": "hijacked", "description": null })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = app.request(Method::DELETE, &uri, Some(&bob), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = app.request(Method::GET, &uri, Some(&alice), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["name"], "alice's archive");
+}
+
+#[sqlx::test]
+async fn tomes_cannot_be_attached_to_or_read_from_other_tenants(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    let archive_id = app.create_archive(&alice, "alice's archive").await;
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/tomes",
+            Some(&bob),
+            Some(tome_payload("tome-bob", &archive_id)),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/tomes",
+            Some(&alice),
+            Some(tome_payload("tome-alice", &archive_id)),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app.request(Method::GET, "/api/v1/tomes", Some(&bob), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!([]));
+
+    for method in [Method::GET, Method::DELETE] {
+        let (status, _) = app
+            .request(method, "/api/v1/tomes/tome-alice", Some(&bob), None)
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    let (status, _) = app
+        .request(
+            Method::PUT,
+            "/api/v1/tomes/tome-alice",
+            Some(&bob),
+            Some(json!({ "name": "hijacked", "description": null })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = app
+        .request(Method::GET, "/api/v1/tomes/tome-alice", Some(&alice), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn sync_push_cannot_overwrite_other_tenants_rows(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    let archive_id = app.create_archive(&alice, "alice's archive").await;
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&bob),
+            Some(json!({
+                "archives": [{
+                    "id": archive_id,
+                    "name": "hijacked",
+                    "description": null,
+                    "created_at": "2020-01-01T00:00:00Z",
+                    "updated_at": "2999-01-01T00:00:00Z",
+                }],
+                "tomes": [],
+                "entries": [],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app
+        .request(Method::GET, &format!("/api/v1/archives/{}", archive_id), Some(&alice), None)
+        .await;
+    assert_eq!(body["name"], "alice's archive");
+}
@@ -0,0 +1,218 @@
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+
+use super::TestApp;
+use crate::tombstones::purge_expired;
+
+async fn create_archive_with_tome(app: &TestApp, token: &str) -> String {
+    let archive_id = app.create_archive(token, "archive").await;
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/tomes",
+            Some(token),
+            Some(json!({ "id": "tome-1", "archive_id": archive_id, "name": "tome", "description": null })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    archive_id
+}
+
+#[sqlx::test]
+async fn deleting_an_archive_hides_it_and_its_tomes(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    let archive_id = create_archive_with_tome(&app, &token).await;
+    let uri = format!("/api/v1/archives/{}", archive_id);
+
+    let (status, _) = app.request(Method::DELETE, &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app.request(Method::GET, &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app
+        .request(Method::GET, "/api/v1/tomes/tome-1", Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = app.request(Method::DELETE, &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // The rows are kept as tombstones for sync clients
+    let tombstones: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM tomes WHERE archive_id = $1 AND deleted_at IS NOT NULL"
+    )
+    .bind(&archive_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(tombstones, 1);
+}
+
+#[sqlx::test]
+async fn deleting_an_entry_answers_like_the_other_deletes(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    create_archive_with_tome(&app, &token).await;
+    let (status, entry) = app
+        .request(
+            Method::POST,
+            "/api/v1/tomes/tome-1/entries",
+            Some(&token),
+            Some(json!({ "title": "notes", "content": "" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", entry);
+    let uri = format!("/api/v1/entries/{}", entry["id"].as_str().unwrap());
+
+    let (status, body) = app.request(Method::DELETE, &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, serde_json::Value::Null);
+
+    let (status, _) = app.request(Method::GET, &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.request(Method::DELETE, &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, changes) = app.request(Method::GET, "/api/v1/sync", Some(&token), None).await;
+    assert_eq!(changes["deleted"]["entries"][0]["id"], entry["id"]);
+}
+
+#[sqlx::test]
+async fn stale_sync_push_does_not_resurrect_a_deleted_archive(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let archive_id = create_archive_with_tome(&app, &token).await;
+    let uri = format!("/api/v1/archives/{}", archive_id);
+
+    app.request(Method::DELETE, &uri, Some(&token), None).await;
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&token),
+            Some(json!({
+                "archives": [{
+                    "id": archive_id,
+                    "name": "edited offline",
+                    "description": null,
+                    "created_at": "2020-01-01T00:00:00Z",
+                    "updated_at": "2999-01-01T00:00:00Z",
+                }],
+                "tomes": [],
+                "entries": [],
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app.request(Method::GET, &uri, Some(&token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn sync_push_applies_client_deletions(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+    let archive_id = create_archive_with_tome(&app, &token).await;
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/api/v1/sync",
+            Some(&token),
+            Some(json!({
+                "archives": [],
+                "tomes": [],
+                "entries": [],
+                "deleted": {
+                    "tomes": [{ "id": "tome-1", "deleted_at": "2026-01-01T00:00:00Z" }],
+                },
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .request(Method::GET, "/api/v1/tomes/tome-1", Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app
+        .request(Method::GET, &format!("/api/v1/archives/{}", archive_id), Some(&token), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn purge_removes_only_expired_tombstones(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    let archive_id = create_archive_with_tome(&app, &token).await;
+    app.request(Method::DELETE, &format!("/api/v1/archives/{}", archive_id), Some(&token), None)
+        .await;
+
+    let purged = purge_expired(&pool, chrono::Duration::days(1)).await.unwrap();
+    assert_eq!(purged, 0);
+
+    let purged = purge_expired(&pool, chrono::Duration::seconds(-1)).await.unwrap();
+    assert_eq!(purged, 2);
+
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM archives")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+}
+
+#[sqlx::test]
+async fn cursors_older_than_a_purge_must_resync(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let token = app.register("alice").await;
+    let pull = |cursor: Option<String>| {
+        let uri = match cursor {
+            Some(cursor) => format!("/api/v1/sync?cursor={}", cursor),
+            None => "/api/v1/sync".to_string(),
+        };
+        let app = &app;
+        let token = &token;
+        async move { app.request(Method::GET, &uri, Some(token), None).await }
+    };
+
+    let archive_id = create_archive_with_tome(&app, &token).await;
+    let (_, body) = pull(None).await;
+    let before_delete = body["cursor"].as_str().unwrap().to_string();
+
+    app.request(Method::DELETE, &format!("/api/v1/archives/{}", archive_id), Some(&token), None)
+        .await;
+    let (_, body) = pull(Some(before_delete.clone())).await;
+    let after_delete = body["cursor"].as_str().unwrap().to_string();
+
+    // Bob's purge watermark is untouched by alice's deletions
+    let bob = app.register("bob").await;
+    let (_, body) = app.request(Method::GET, "/api/v1/sync", Some(&bob), None).await;
+    let bobs_cursor = body["cursor"].as_str().unwrap().to_string();
+
+    purge_expired(&pool, chrono::Duration::seconds(-1)).await.unwrap();
+
+    let (status, body) = pull(Some(before_delete)).await;
+    assert_eq!(status, StatusCode::GONE, "{}", body);
+    assert_eq!(body["code"], "resync_required");
+
+    // Clients that already saw the tombstones carry on as before
+    let (status, _) = pull(Some(after_delete)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .request(Method::GET, &format!("/api/v1/sync?cursor={}", bobs_cursor), Some(&bob), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // A full pull starts the client over without the purged rows
+    let (status, body) = pull(None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["archives"], serde_json::json!([]));
+    let (status, _) = pull(Some(body["cursor"].as_str().unwrap().to_string())).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,73 @@
+use axum::http::{Method, StatusCode};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+#[sqlx::test]
+async fn only_administrators_list_users(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (status, body) = app.request(Method::GET, "/api/v1/users", Some(&token), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "forbidden");
+
+    let (status, _) = app.request(Method::GET, "/api/v1/users", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn profiles_never_expose_hashes_or_other_peoples_emails(pool: PgPool) {
+    let app = TestApp::new(pool.clone());
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    let (status, me) = app.request(Method::GET, "/api/v1/users/me", Some(&alice), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", me);
+    assert_eq!(me["username"], "alice");
+    assert!(me["email"].is_string());
+    assert!(me.get("password_hash").is_none());
+
+    let (status, profile) = app
+        .request(Method::GET, &format!("/api/v1/users/{}", me["id"].as_str().unwrap()), Some(&bob), None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(profile["username"], "alice");
+    assert!(profile.get("email").is_none());
+    assert!(profile.get("password_hash").is_none());
+
+    sqlx::query("UPDATE users SET is_admin = true WHERE username = 'bob'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let (status, users) = app.request(Method::GET, "/api/v1/users?sort=name", Some(&bob), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", users);
+    let users = users.as_array().unwrap();
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0]["email"], me["email"]);
+    assert!(users.iter().all(|user| user.get("password_hash").is_none()));
+}
+
+#[sqlx::test]
+async fn owners_update_their_own_profile(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let (_, me) = app.request(Method::GET, "/api/v1/users/me", Some(&alice), None).await;
+    let uri = format!("/api/v1/users/{}", me["id"].as_str().unwrap());
+    let changes = serde_json::json!({ "username": "alicia", "email": "alicia@example.com" });
+
+    let (status, _) = app.request(Method::PUT, &uri, Some(&bob), Some(changes.clone())).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, updated) = app.request(Method::PUT, &uri, Some(&alice), Some(changes)).await;
+    assert_eq!(status, StatusCode::OK, "{}", updated);
+    assert_eq!(updated["username"], "alicia");
+    assert_eq!(updated["email"], "alicia@example.com");
+    assert_eq!(updated["created_at"], me["created_at"]);
+    assert!(updated["updated_at"].as_str() > me["updated_at"].as_str(), "{}", updated);
+    assert!(updated.get("password_hash").is_none());
+
+    let (_, me) = app.request(Method::GET, "/api/v1/users/me", Some(&alice), None).await;
+    assert_eq!(me["updated_at"], updated["updated_at"]);
+}
@@ -0,0 +1,103 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use super::TestApp;
+
+fn invalid_fields(body: &Value) -> Vec<&str> {
+    assert_eq!(body["code"], "validation_failed", "{}", body);
+    body["details"]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect()
+}
+
+#[sqlx::test]
+async fn invalid_payloads_list_every_bad_field(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (status, body) = app
+        .request(Method::POST, "/api/v1/archives", Some(&token), Some(json!({ "name": "   " })))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(invalid_fields(&body), ["name"]);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/tomes",
+            Some(&token),
+            Some(json!({ "id": "has spaces", "archive_id": "", "name": "x".repeat(256) })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(invalid_fields(&body), ["id", "archive_id", "name"]);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/entries",
+            Some(&token),
+            Some(json!({ "tome_id": "tome-1", "title": "", "content": "" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(invalid_fields(&body), ["title"]);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/api/v1/auth/register",
+            None,
+            Some(json!({ "username": "bob", "email": "not-an-email", "password": "short" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(invalid_fields(&body), ["email", "password"]);
+
+    // Missing fields are reported by the JSON decoder before validation
+    let (status, body) = app
+        .request(Method::POST, "/api/v1/archives", Some(&token), Some(json!({})))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "invalid_body");
+}
+
+#[sqlx::test]
+async fn server_owned_fields_are_ignored(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.register("alice").await;
+
+    let (status, archive) = app
+        .request(
+            Method::POST,
+            "/api/v1/archives",
+            Some(&token),
+            Some(json!({ "name": "archive", "id": "archive-spoofed", "user_id": uuid::Uuid::nil() })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", archive);
+    assert_ne!(archive["id"], "archive-spoofed");
+    assert_eq!(archive["description"], Value::Null);
+
+    let (status, tome) = app
+        .request(
+            Method::POST,
+            "/api/v1/tomes",
+            Some(&token),
+            Some(json!({
+                "archive_id": archive["id"],
+                "name": "tome",
+                "user_id": uuid::Uuid::nil(),
+                "created_at": "2000-01-01T00:00:00",
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", tome);
+    assert!(tome["id"].as_str().unwrap().starts_with("tome-"));
+    assert_eq!(tome["user_id"], archive["user_id"]);
+    assert_ne!(tome["created_at"], "2000-01-01T00:00:00");
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+// How often the purge task looks for expired tombstones
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Marks an archive as deleted along with every tome and entry beneath it.
+///
+/// Deletions always win over edits: once a row is a tombstone, later
+/// upserts from sync clients leave it alone. Returns `false` when the
+/// archive does not exist, belongs to someone else or is already deleted.
+pub async fn delete_archive(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: &str,
+    deleted_at: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE archives SET deleted_at = $3, updated_at = GREATEST(updated_at, $3)
+         WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        user_id,
+        deleted_at.naive_utc()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "UPDATE entries SET deleted_at = $3, updated_at = GREATEST(updated_at, $3)
+         WHERE user_id = $2 AND deleted_at IS NULL
+           AND tome_id IN (SELECT id FROM tomes WHERE archive_id = $1)",
+        id,
+        user_id,
+        deleted_at.naive_utc()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE tomes SET deleted_at = $3, updated_at = GREATEST(updated_at, $3)
+         WHERE archive_id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        user_id,
+        deleted_at.naive_utc()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
+
+/// Marks a tome as deleted along with its entries.
+pub async fn delete_tome(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: &str,
+    deleted_at: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE tomes SET deleted_at = $3, updated_at = GREATEST(updated_at, $3)
+         WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        user_id,
+        deleted_at.naive_utc()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "UPDATE entries SET deleted_at = $3, updated_at = GREATEST(updated_at, $3)
+         WHERE tome_id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        user_id,
+        deleted_at.naive_utc()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
+
+/// Marks a single entry as deleted.
+pub async fn delete_entry(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: &str,
+    deleted_at: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE entries SET deleted_at = $3, updated_at = GREATEST(updated_at, $3)
+         WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        id,
+        user_id,
+        deleted_at.naive_utc()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Permanently removes tombstones older than `retention`, returning how
+/// many rows were purged. Each affected user's `purged_through` watermark
+/// is raised to the newest purged revision, so clients whose cursor
+/// predates it are told to resync instead of silently missing deletions.
+pub async fn purge_expired(pool: &PgPool, retention: Duration) -> Result<u64, sqlx::Error> {
+    let cutoff = (Utc::now() - retention).naive_utc();
+    let mut tx = pool.begin().await?;
+
+    let mut purged = sqlx::query_scalar!(
+        r#"WITH purged AS (
+             DELETE FROM entries WHERE deleted_at < $1 RETURNING user_id, revision
+         ), watermarks AS (
+             UPDATE users SET purged_through = GREATEST(users.purged_through, p.revision)
+             FROM (SELECT user_id, MAX(revision) AS revision FROM purged GROUP BY user_id) p
+             WHERE users.id = p.user_id
+         )
+         SELECT COUNT(*) AS "count!" FROM purged"#,
+        cutoff
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    purged += sqlx::query_scalar!(
+        r#"WITH purged AS (
+             DELETE FROM tomes WHERE deleted_at < $1 RETURNING user_id, revision
+         ), watermarks AS (
+             UPDATE users SET purged_through = GREATEST(users.purged_through, p.revision)
+             FROM (SELECT user_id, MAX(revision) AS revision FROM purged GROUP BY user_id) p
+             WHERE users.id = p.user_id
+         )
+         SELECT COUNT(*) AS "count!" FROM purged"#,
+        cutoff
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    purged += sqlx::query_scalar!(
+        r#"WITH purged AS (
+             DELETE FROM archives WHERE deleted_at < $1 RETURNING user_id, revision
+         ), watermarks AS (
+             UPDATE users SET purged_through = GREATEST(users.purged_through, p.revision)
+             FROM (SELECT user_id, MAX(revision) AS revision FROM purged GROUP BY user_id) p
+             WHERE users.id = p.user_id
+         )
+         SELECT COUNT(*) AS "count!" FROM purged"#,
+        cutoff
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(purged as u64)
+}
+
+/// Runs `purge_expired` in the background for the lifetime of the server.
+pub fn spawn_purge_task(pool: PgPool, retention: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match purge_expired(&pool, retention).await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!(purged, "Purged expired tombstones"),
+                Err(e) => tracing::warn!(error = %e, "Failed to purge expired tombstones"),
+            }
+        }
+    })
+}
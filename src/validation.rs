@@ -0,0 +1,162 @@
+//! Request body validation.
+//!
+//! Payload types implement [`Validate`] and are extracted with
+//! [`ValidJson`], which answers with a 422 listing every offending field
+//! instead of failing on the first one or on a database constraint.
+
+use axum::{
+    async_trait,
+    body::HttpBody,
+    extract::{FromRequest, Json},
+    http::{Request, StatusCode},
+    BoxError,
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::json;
+
+use crate::error::ApiError;
+use crate::ordering::MAX_POSITION;
+
+/// Longest value a `VARCHAR(255)` column accepts, in characters.
+pub const MAX_VARCHAR_LENGTH: usize = 255;
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+pub trait Validate {
+    fn validate(&self, errors: &mut FieldErrors);
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Collects problems with individual fields of a payload.
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &'static str, message: impl Into<String>) {
+        self.0.push(FieldError { field, message: message.into() });
+    }
+
+    /// A name or title: present, not blank and short enough for its column.
+    pub fn name(&mut self, field: &'static str, value: &str) {
+        if value.trim().is_empty() {
+            self.add(field, "must not be empty");
+        } else {
+            self.max_length(field, value);
+        }
+    }
+
+    /// A name that cannot be mistaken for an email address when logging in.
+    pub fn username(&mut self, field: &'static str, value: &str) {
+        if value.contains('@') {
+            self.add(field, "must not contain `@`");
+        } else {
+            self.name(field, value);
+        }
+    }
+
+    /// A client-chosen id, when one was supplied.
+    pub fn optional_id(&mut self, field: &'static str, value: Option<&str>) {
+        if let Some(value) = value {
+            self.id(field, value);
+        }
+    }
+
+    pub fn id(&mut self, field: &'static str, value: &str) {
+        if value.is_empty() || value.chars().any(char::is_whitespace) {
+            self.add(field, "must be non-empty and contain no whitespace");
+        } else {
+            self.max_length(field, value);
+        }
+    }
+
+    /// A requested sibling position, when one was supplied.
+    pub fn position(&mut self, field: &'static str, value: Option<i32>) {
+        if value.is_some_and(|position| !(0..=MAX_POSITION).contains(&position)) {
+            self.add(field, format!("must be between 0 and {}", MAX_POSITION));
+        }
+    }
+
+    pub fn max_length(&mut self, field: &'static str, value: &str) {
+        if value.chars().count() > MAX_VARCHAR_LENGTH {
+            self.add(field, format!("must be at most {} characters", MAX_VARCHAR_LENGTH));
+        }
+    }
+
+    pub fn email(&mut self, field: &'static str, value: &str) {
+        let well_formed = value.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.split('.').count() >= 2
+                && domain.split('.').all(|label| !label.is_empty())
+        });
+        if !well_formed || value.chars().any(char::is_whitespace) {
+            self.add(field, "must be a valid email address");
+        } else {
+            self.max_length(field, value);
+        }
+    }
+
+    pub fn password(&mut self, field: &'static str, value: &str) {
+        if value.chars().count() < MIN_PASSWORD_LENGTH {
+            self.add(field, format!("must be at least {} characters", MIN_PASSWORD_LENGTH));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), ApiError> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "validation_failed",
+            "Some fields are invalid",
+        )
+        .with_details(json!({ "fields": self.0 })))
+    }
+}
+
+/// For `PATCH` fields that may be cleared: tells an explicit `null`
+/// (`Some(None)`) apart from an absent field (`None`). Use together with
+/// `#[serde(default)]`.
+pub fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// JSON body extractor that runs [`Validate`] after deserializing.
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| ApiError::new(rejection.status(), "invalid_body", rejection.body_text()))?;
+
+        let mut errors = FieldErrors::default();
+        value.validate(&mut errors);
+        errors.into_result()?;
+
+        Ok(ValidJson(value))
+    }
+}